@@ -1,15 +1,49 @@
-use std::{collections::HashMap, sync::Arc};
+use crate::{Number, constant::Constant, expr::ExprRef, functions::{Asinh, Atan, Atan2, Cos, Cosh, Cot, Csc, Exp, Plus, Pow, Sec, Sin, Sinh, Tan, Tanh, Times}, symbol::Symbol};
 
-use crate::basic::Thing;
-
-pub struct Assumptions {
-    positive: HashMap<Arc<Thing>, bool>,
-}
-
-impl Assumptions {
-    pub fn new() -> Self {
-        Self {
-            positive: HashMap::new(),
-        }
+/// Returns `true` when `expr` is known to only take real values.
+///
+/// This is conservative, so `false` means "not known to be real"
+/// rather than "not real".
+pub fn is_real(expr: ExprRef) -> bool {
+    if let Some(n) = expr.downcast::<Number>() {
+        !matches!(n, Number::Infinity(_))
+    } else if let Some(sym) = expr.downcast::<Symbol>() {
+        sym.is_real()
+    } else if let Some(plus) = expr.downcast::<Plus>() {
+        plus.terms().iter().all(|term| is_real(term.rf()))
+    } else if let Some(times) = expr.downcast::<Times>() {
+        times.terms().iter().all(|term| is_real(term.rf()))
+    } else if let Some(pow) = expr.downcast::<Pow>() {
+        is_real(pow.base()) && pow.exp().downcast::<Number>().is_some_and(Number::is_integer)
+    } else if let Some(c) = expr.downcast::<Constant>() {
+        c.is_real()
+    } else if let Some(exp) = expr.downcast::<Exp>() {
+        is_real(exp.arg())
+    } else if let Some(sin) = expr.downcast::<Sin>() {
+        is_real(sin.arg())
+    } else if let Some(cos) = expr.downcast::<Cos>() {
+        is_real(cos.arg())
+    } else if let Some(tan) = expr.downcast::<Tan>() {
+        is_real(tan.arg())
+    } else if let Some(cot) = expr.downcast::<Cot>() {
+        is_real(cot.arg())
+    } else if let Some(sec) = expr.downcast::<Sec>() {
+        is_real(sec.arg())
+    } else if let Some(csc) = expr.downcast::<Csc>() {
+        is_real(csc.arg())
+    } else if let Some(atan) = expr.downcast::<Atan>() {
+        is_real(atan.arg())
+    } else if let Some(atan2) = expr.downcast::<Atan2>() {
+        is_real(atan2.y()) && is_real(atan2.x())
+    } else if let Some(sinh) = expr.downcast::<Sinh>() {
+        is_real(sinh.arg())
+    } else if let Some(cosh) = expr.downcast::<Cosh>() {
+        is_real(cosh.arg())
+    } else if let Some(tanh) = expr.downcast::<Tanh>() {
+        is_real(tanh.arg())
+    } else if let Some(asinh) = expr.downcast::<Asinh>() {
+        is_real(asinh.arg())
+    } else {
+        false
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
-use std::{any::{Any, TypeId}, cmp::Ordering, fmt::Debug, ops::{Add, ControlFlow, Mul}};
+use std::{any::{Any, TypeId}, cmp::Ordering, fmt::Debug, ops::{Add, ControlFlow, Div, Mul, Neg, Sub}};
 
-use crate::{expr::{Expr, ExprRef}, functions, visitor::Visitor};
+use crate::{expr::{Expr, ExprRef}, functions, number::MINUS_ONE, visitor::Visitor};
 
 pub trait Basic: Send + Sync + Debug + Any {
     fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()>;
@@ -8,6 +8,52 @@ pub trait Basic: Send + Sync + Debug + Any {
     /// In implementations of this, return `None` when
     /// the types do not match.
     fn cmp(&self, other: &dyn Basic) -> Option<Ordering>;
+
+    // The built-in passes use `args` and `with_args` to walk the tree. The
+    // other methods below are only used for node types that the built-in
+    // passes don't know about.
+
+    /// The name used when printing this node.
+    fn name(&self) -> &str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    /// The sub-expressions of this node. Leaves have none.
+    fn args(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    /// Rebuilds this node from new sub-expressions, given in the same
+    /// order as `args`. Returning `None` leaves the node as it is.
+    fn with_args(&self, _args: Vec<Expr>) -> Option<Expr> {
+        None
+    }
+
+    /// Canonicalizes this node, whose args are already canonical.
+    /// Returning `None` leaves the node as it is.
+    fn canonical(&self) -> Option<Expr> {
+        None
+    }
+
+    /// Evaluates this node, whose args are already evaluated.
+    /// Returning `None` leaves the node as it is.
+    fn eval_symbolic(&self) -> Option<Expr> {
+        None
+    }
+
+    /// The derivative of this node with respect to its `i`th argument,
+    /// which the chain rule is applied to. Returning `None` leaves the
+    /// derivative unevaluated.
+    fn partial_derivative(&self, _i: usize) -> Option<Expr> {
+        None
+    }
+
+    /// Writes this node as LaTeX, given its `args` already written as
+    /// LaTeX. Returning `None` writes it as `\operatorname{name}(args)`.
+    fn latex(&self, _args: &[String]) -> Option<String> {
+        None
+    }
 }
 
 impl dyn Basic {
@@ -27,7 +73,7 @@ impl dyn Basic {
 }
 
 impl Expr {
-    pub fn downcast_expr<'a, T: Basic>(&'a self) -> Result<Expr<T>, &'a Expr> {
+    pub fn downcast_expr<T: Basic>(&self) -> Result<Expr<T>, &Expr> {
         if self.is::<T>() {
             unsafe {
                 Ok(Expr::clone(&*(self as *const Expr as *const Expr<T>)))
@@ -96,6 +142,200 @@ where
     }
 }
 
+impl<A, B> Sub<B> for Expr<A>
+where
+    A: Basic,
+    B: Into<Expr>,
+{
+    type Output = Expr;
+
+    fn sub(self, rhs: B) -> Self::Output {
+        (self as Expr) - rhs
+    }
+}
+
+impl<B> Sub<B> for Expr
+where
+    B: Into<Expr>,
+{
+    type Output = Expr;
+
+    /// Lowered to `self + (-1 * rhs)`.
+    fn sub(self, rhs: B) -> Self::Output {
+        let negated = functions::times(MINUS_ONE.rf(), rhs.into().rf());
+        functions::plus(self.rf(), negated.rf())
+    }
+}
+
+impl<A, B> Div<B> for Expr<A>
+where
+    A: Basic,
+    B: Into<Expr>,
+{
+    type Output = Expr;
+
+    fn div(self, rhs: B) -> Self::Output {
+        (self as Expr) / rhs
+    }
+}
+
+impl<B> Div<B> for Expr
+where
+    B: Into<Expr>,
+{
+    type Output = Expr;
+
+    /// Lowered to `self * rhs^-1`.
+    fn div(self, rhs: B) -> Self::Output {
+        let recip = functions::pow(rhs.into().rf(), MINUS_ONE.rf());
+        functions::times(self.rf(), recip.rf())
+    }
+}
+
+impl<A: Basic> Neg for Expr<A> {
+    type Output = Expr;
+
+    fn neg(self) -> Self::Output {
+        -(self as Expr)
+    }
+}
+
+impl Neg for Expr {
+    type Output = Expr;
+
+    /// Lowered to `-1 * self`.
+    fn neg(self) -> Self::Output {
+        functions::times(MINUS_ONE.rf(), self.rf())
+    }
+}
+
+/// Forwards the operators on borrowed expressions to the owned impls above.
+macro_rules! impl_borrowed_ops {
+    ($([$($generics:tt)*] $ty:ty => |$this:ident| $owned:expr;)*) => {$(
+        impl<$($generics)* B: Into<Expr>> Add<B> for $ty {
+            type Output = Expr;
+
+            fn add(self, rhs: B) -> Self::Output {
+                let $this = self;
+                $owned + rhs
+            }
+        }
+
+        impl<$($generics)* B: Into<Expr>> Sub<B> for $ty {
+            type Output = Expr;
+
+            fn sub(self, rhs: B) -> Self::Output {
+                let $this = self;
+                $owned - rhs
+            }
+        }
+
+        impl<$($generics)* B: Into<Expr>> Mul<B> for $ty {
+            type Output = Expr;
+
+            fn mul(self, rhs: B) -> Self::Output {
+                let $this = self;
+                $owned * rhs
+            }
+        }
+
+        impl<$($generics)* B: Into<Expr>> Div<B> for $ty {
+            type Output = Expr;
+
+            fn div(self, rhs: B) -> Self::Output {
+                let $this = self;
+                $owned / rhs
+            }
+        }
+
+        impl<$($generics)*> Neg for $ty {
+            type Output = Expr;
+
+            fn neg(self) -> Self::Output {
+                let $this = self;
+                -$owned
+            }
+        }
+    )*};
+}
+
+impl_borrowed_ops! {
+    ['a, A: Basic,] &'a Expr<A> => |e| e.clone();
+    ['a,] &'a Expr => |e| e.clone();
+    ['a, A: Basic,] ExprRef<'a, A> => |e| e.into_expr();
+    ['a,] ExprRef<'a> => |e| e.into_expr();
+}
+
+/// Implements the operators with a primitive on the left-hand side,
+/// so that `2 * x` works as well as `x * 2`.
+macro_rules! impl_scalar_ops {
+    ($($scalar:ty),*) => {$(
+        impl_scalar_ops!(@op $scalar, Add, add, +);
+        impl_scalar_ops!(@op $scalar, Sub, sub, -);
+        impl_scalar_ops!(@op $scalar, Mul, mul, *);
+        impl_scalar_ops!(@op $scalar, Div, div, /);
+    )*};
+    (@op $scalar:ty, $trait:ident, $method:ident, $op:tt) => {
+        impl<A: Basic> $trait<Expr<A>> for $scalar {
+            type Output = Expr;
+
+            fn $method(self, rhs: Expr<A>) -> Self::Output {
+                Expr::from(self) $op rhs
+            }
+        }
+
+        impl $trait<Expr> for $scalar {
+            type Output = Expr;
+
+            fn $method(self, rhs: Expr) -> Self::Output {
+                Expr::from(self) $op rhs
+            }
+        }
+
+        impl<'a, A: Basic> $trait<&'a Expr<A>> for $scalar {
+            type Output = Expr;
+
+            fn $method(self, rhs: &'a Expr<A>) -> Self::Output {
+                Expr::from(self) $op rhs
+            }
+        }
+
+        impl<'a> $trait<&'a Expr> for $scalar {
+            type Output = Expr;
+
+            fn $method(self, rhs: &'a Expr) -> Self::Output {
+                Expr::from(self) $op rhs
+            }
+        }
+    };
+}
+
+impl_scalar_ops!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl<A: Basic> Expr<A> {
+    pub fn pow<B: Into<Expr>>(self, exp: B) -> Expr {
+        functions::pow((self as Expr).rf(), exp.into().rf())
+    }
+}
+
+impl Expr {
+    pub fn pow<B: Into<Expr>>(self, exp: B) -> Expr {
+        functions::pow(self.rf(), exp.into().rf())
+    }
+}
+
+impl<A: Basic> ExprRef<'_, A> {
+    pub fn pow<B: Into<Expr>>(self, exp: B) -> Expr {
+        self.into_expr().pow(exp)
+    }
+}
+
+impl ExprRef<'_> {
+    pub fn pow<B: Into<Expr>>(self, exp: B) -> Expr {
+        self.into_expr().pow(exp)
+    }
+}
+
 impl PartialEq for dyn Basic {
     fn eq(&self, other: &Self) -> bool {
         <Self as Basic>::eq(self, other)
@@ -106,7 +346,9 @@ impl Eq for dyn Basic {}
 
 impl PartialOrd for dyn Basic {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        <Self as Basic>::cmp(self, other)
+        // Expressions of different types still need a consistent order,
+        // otherwise sorting the terms of a `Plus` or `Times` is not stable.
+        Some(Ord::cmp(self, other))
     }
 }
 
@@ -1,124 +1,299 @@
 use std::ops::ControlFlow;
 
-use crate::{Number, basic::Basic, expr::{Expr, ExprRef}, functions::{Derivative, Plus, Times}, symbol::Symbol, undefined::{Undefined, UNDEFINED}, visitor::Visitor, number::{ZERO, ONE}};
+use num::{Integer, ToPrimitive};
 
-pub fn canonicalize(expr: Expr) -> Expr {
-    let mut visitor = CanonicalizeVisitor {
-        res: UNDEFINED.clone(),
-    };
+use crate::{Number, basic::Basic, constant::{Constant, I, PI}, expr::{Expr, ExprRef}, fold::{Transformer, bottom_up}, functions::{Acos, Asin, Asinh, Atan, Atanh, Cos, Cosh, Csc, Cot, Exp, Plus, Pow, Sec, Sin, Sinh, Tan, Tanh, Times}, undefined::{Undefined, UNDEFINED}, number::{ZERO, ONE, MINUS_ONE}};
 
-    match expr.rf().visit(&mut visitor) {
-        ControlFlow::Continue(_) => visitor.res,
+pub fn canonicalize(expr: Expr) -> Expr {
+    match bottom_up(expr.rf(), &mut Canonicalize) {
+        ControlFlow::Continue(res) => res,
         ControlFlow::Break(_) => UNDEFINED.clone(),
     }
 }
 
-struct CanonicalizeVisitor {
-    res: Expr,
-}
+/// Combines terms that are already canonical into a canonical sum.
+fn canonical_plus(terms: impl IntoIterator<Item = Expr>) -> Expr {
+    let mut v = vec![];
+    let mut coef: Option<Number> = None;
+    for term in terms {
+        if let Ok(n) = term.downcast_expr::<Number>() {
+            if n.is_zero() {
+                continue;
+            }
 
-impl Visitor for CanonicalizeVisitor {
-    fn visit_undefined(&mut self, _undef: ExprRef<Undefined>) -> ControlFlow<()> {
-        ControlFlow::Break(())
+            if let Some(coef) = coef.as_mut() {
+                *coef = &*coef + &*n;
+            } else {
+                coef = Some((*n).clone());
+            }
+        } else if let Some(plus_term) = term.downcast::<Plus>() {
+            v.extend_from_slice(plus_term.terms());
+        } else {
+            v.push(term.clone());
+        }
     }
 
-    fn visit_number(&mut self, n: ExprRef<Number>) -> ControlFlow<()> {
-        if let Number::Rational(r) = &*n {
-            self.res = Expr::new(Number::Integer(r.numer().clone()))
-        } else {
-            self.res = n.into_expr();
+    if let Some(coef) = coef {
+        if !coef.is_zero() {
+            v.push(Expr::new(coef));
         }
+    }
 
-        ControlFlow::Continue(())
+    if v.is_empty() {
+        ZERO.clone()
+    } else if v.len() == 1 {
+        v[0].clone()
+    } else {
+        Expr::new(Plus::new(v))
     }
+}
 
-    fn visit_symbol(&mut self, sym: ExprRef<Symbol>) -> ControlFlow<()> {
-        self.res = sym.into_expr();
-        ControlFlow::Continue(())
+/// Combines factors that are already canonical into a canonical product.
+///
+/// Repeated factors are merged into powers, so `x * x * x` becomes `x^3`
+/// and `x^a * x^b` becomes `x^(a + b)`.
+fn canonical_times(factors: impl IntoIterator<Item = Expr>) -> ControlFlow<(), Expr> {
+    let mut powers: Vec<(Expr, Vec<Expr>)> = vec![];
+    let mut coef: Option<Number> = None;
+
+    fn push_coef(coef: &mut Option<Number>, n: &Number) {
+        if let Some(coef) = coef.as_mut() {
+            *coef = &*coef * n;
+        } else {
+            *coef = Some(n.clone());
+        }
     }
 
-    fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()> {
-        let mut v = vec![];
-        let mut coef: Option<Number> = None;
-        for term in plus.terms() {
-            term.rf().visit(self);
-            let term = self.res.clone();
-            
-            if let Ok(n) = term.downcast_expr::<Number>() {
-                if n.is_zero() {
-                    continue;
-                }
+    for factor in factors {
+        if let Some(n) = factor.downcast::<Number>() {
+            if n.is_one() {
+                continue;
+            } else if n.is_zero() {
+                return ControlFlow::Continue(ZERO.clone());
+            }
 
-                if let Some(coef) = coef.as_mut() {
-                    *coef = &*coef + &*n;
+            push_coef(&mut coef, n);
+        } else if let Some(times_factor) = factor.downcast::<Times>() {
+            for factor in times_factor.terms() {
+                if let Some(n) = factor.downcast::<Number>() {
+                    push_coef(&mut coef, n);
                 } else {
-                    coef = Some((&*n).clone());
+                    push_power(&mut powers, factor.clone());
                 }
-            } else if let Some(plus_term) = term.downcast::<Plus>() {
-                v.extend_from_slice(plus_term.terms());
+            }
+        } else {
+            push_power(&mut powers, factor);
+        }
+    }
+
+    let mut v = vec![];
+    for (base, mut exps) in powers {
+        let merged = if exps.len() == 1 {
+            let exp = exps.pop().unwrap();
+            if exp.downcast::<Number>().is_some_and(Number::is_one) {
+                base
             } else {
-                v.push(term.clone());
+                Expr::new(Pow::new(base, exp))
+            }
+        } else {
+            canonical_pow(base, canonical_plus(exps))?
+        };
+
+        if let Some(n) = merged.downcast::<Number>() {
+            if n.is_zero() {
+                return ControlFlow::Continue(ZERO.clone());
             }
+            push_coef(&mut coef, n);
+        } else {
+            v.push(merged);
         }
+    }
 
-        if let Some(coef) = coef {
+    if let Some(coef) = coef {
+        if coef.is_zero() {
+            return ControlFlow::Continue(ZERO.clone());
+        } else if !coef.is_one() {
             v.push(Expr::new(coef));
         }
+    }
 
-        self.res = if v.len() == 0 {
-            ZERO.clone()
-        } else if v.len() == 1 {
-            v[0].clone()
-        } else {
-            Expr::new(Plus::new(v))
-        };
+    ControlFlow::Continue(if v.is_empty() {
+        ONE.clone()
+    } else if v.len() == 1 {
+        v[0].clone()
+    } else {
+        Expr::new(Times::new(v))
+    })
+}
 
-        ControlFlow::Continue(())
-    }
-
-    fn visit_times(&mut self, times: ExprRef<Times>) -> ControlFlow<()> {
-        let mut v = vec![];
-        let mut coef: Option<Number> = None;
-        for term in times.terms() {
-            term.rf().visit(self);
-            let term = self.res.clone();
-            
-            if let Some(n) = term.downcast::<Number>() {
-                if n.is_one() {
-                    continue;
-                } else if n.is_zero() {
-                    self.res = ZERO.clone();
-                    return ControlFlow::Continue(());
-                }
+/// Records `factor` as `base ^ exp`, grouping it with any earlier
+/// factor that shares the same base.
+fn push_power(powers: &mut Vec<(Expr, Vec<Expr>)>, factor: Expr) {
+    let (base, exp) = match factor.downcast::<Pow>() {
+        Some(pow) => (pow.base().into_expr(), pow.exp().into_expr()),
+        None => (factor, ONE.clone() as Expr),
+    };
 
-                if let Some(coef) = coef.as_mut() {
-                    *coef = &*coef * &*n;
-                } else {
-                    coef = Some((&*n).clone());
-                }
-            } else if let Some(times_term) = term.downcast::<Times>() {
-                v.extend_from_slice(times_term.terms());
-            } else {
-                v.push(term.clone());
+    if let Some((_, exps)) = powers.iter_mut().find(|(b, _)| PartialEq::eq(b, &base)) {
+        exps.push(exp);
+    } else {
+        powers.push((base, vec![exp]));
+    }
+}
+
+/// Builds a canonical `base ^ exp` from a base and exponent that are already canonical.
+fn canonical_pow(base: Expr, exp: Expr) -> ControlFlow<(), Expr> {
+    if let Some(e) = exp.downcast::<Number>() {
+        if e.is_zero() {
+            return ControlFlow::Continue(ONE.clone());
+        } else if e.is_one() {
+            return ControlFlow::Continue(base);
+        }
+
+        if let Some(b) = base.downcast::<Number>() {
+            if b.is_zero() && e.is_negative() {
+                // division by zero
+                return ControlFlow::Break(());
+            }
+
+            if let Some(n) = b.pow(e) {
+                return ControlFlow::Continue(Expr::new(n));
             }
         }
 
-        if let Some(coef) = coef {
-            v.push(Expr::new(coef));
+        if base.downcast::<Constant>() == Some(&Constant::I) {
+            if let Number::Integer(n) = e {
+                // i^n cycles through 1, i, -1, -i
+                return ControlFlow::Continue(match n.mod_floor(&4.into()).to_u8() {
+                    Some(0) => ONE.clone(),
+                    Some(1) => I.clone(),
+                    Some(2) => MINUS_ONE.clone(),
+                    _ => canonical_times([MINUS_ONE.clone() as Expr, I.clone()])?,
+                });
+            }
         }
 
-        self.res = if v.len() == 0 {
-            ONE.clone()
-        } else if v.len() == 1 {
-            v[0].clone()
+        if e.is_integer() {
+            // (b^e1)^e2 = b^(e1*e2) is only valid for integer e2
+            if let Some(inner) = base.downcast::<Pow>() {
+                let exp = canonical_times([inner.exp().into_expr(), exp.clone()])?;
+                return canonical_pow(inner.base().into_expr(), exp);
+            }
+        }
+    }
+
+    if let Some(b) = base.downcast::<Number>() {
+        if b.is_one() {
+            return ControlFlow::Continue(ONE.clone());
+        }
+    }
+
+    if base.downcast::<Constant>() == Some(&Constant::E) {
+        return ControlFlow::Continue(Expr::new(Exp::new(exp)));
+    }
+
+    ControlFlow::Continue(Expr::new(Pow::new(base, exp)))
+}
+
+/// If `expr` has a negative numeric coefficient, returns `-expr`.
+fn extract_minus_sign(expr: &Expr) -> Option<Expr> {
+    if let Some(n) = expr.downcast::<Number>() {
+        return if n.is_negative() {
+            Some(Expr::new(n * &**MINUS_ONE))
         } else {
-            Expr::new(Times::new(v))
+            None
         };
+    }
+
+    let times = expr.downcast::<Times>()?;
+    let coef = times.terms().iter().find_map(|term| term.downcast::<Number>())?;
+    if !coef.is_negative() {
+        return None;
+    }
+
+    let mut v: Vec<Expr> = times.terms().iter().filter(|term| !term.is::<Number>()).cloned().collect();
+    let negated = coef * &**MINUS_ONE;
+    if !negated.is_one() {
+        v.push(Expr::new(negated));
+    }
+
+    Some(if v.len() == 1 {
+        v.pop().unwrap()
+    } else {
+        Expr::new(Times::new(v))
+    })
+}
+
+/// Canonicalizes a single node whose args are already canonical.
+pub struct Canonicalize;
 
-        ControlFlow::Continue(())
+/// Canonicalizes `expr = f(arg)` for an odd function, so `f(-x) = -f(x)`.
+fn odd<T: Basic>(expr: Expr, arg: ExprRef, f: impl Fn(Expr) -> T) -> ControlFlow<(), Expr> {
+    ControlFlow::Continue(match extract_minus_sign(&arg.into_expr()) {
+        Some(arg) => canonical_times([MINUS_ONE.clone() as Expr, Expr::new(f(arg))])?,
+        None => expr,
+    })
+}
+
+/// Canonicalizes `expr = f(arg)` for an even function, so `f(-x) = f(x)`.
+fn even<T: Basic>(expr: Expr, arg: ExprRef, f: impl Fn(Expr) -> T) -> Expr {
+    match extract_minus_sign(&arg.into_expr()) {
+        Some(arg) => Expr::new(f(arg)),
+        None => expr,
     }
+}
+
+impl Transformer for Canonicalize {
+    fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+        if expr.is::<Undefined>() {
+            return ControlFlow::Break(());
+        }
 
-    fn visit_derivative(&mut self, derivative: ExprRef<Derivative>) -> ControlFlow<()> {
-        derivative.visit(self)
+        ControlFlow::Continue(if let Some(Number::Rational(r)) = expr.downcast::<Number>() {
+            Expr::new(Number::from_rational(r.clone()))
+        } else if let Some(plus) = expr.downcast::<Plus>() {
+            canonical_plus(plus.terms().iter().cloned())
+        } else if let Some(times) = expr.downcast::<Times>() {
+            canonical_times(times.terms().iter().cloned())?
+        } else if let Some(pow) = expr.downcast::<Pow>() {
+            canonical_pow(pow.base().into_expr(), pow.exp().into_expr())?
+        } else if let Some(sin) = expr.downcast::<Sin>() {
+            odd(expr.clone(), sin.arg(), Sin::new)?
+        } else if let Some(tan) = expr.downcast::<Tan>() {
+            odd(expr.clone(), tan.arg(), Tan::new)?
+        } else if let Some(cot) = expr.downcast::<Cot>() {
+            odd(expr.clone(), cot.arg(), Cot::new)?
+        } else if let Some(csc) = expr.downcast::<Csc>() {
+            odd(expr.clone(), csc.arg(), Csc::new)?
+        } else if let Some(asin) = expr.downcast::<Asin>() {
+            odd(expr.clone(), asin.arg(), Asin::new)?
+        } else if let Some(atan) = expr.downcast::<Atan>() {
+            odd(expr.clone(), atan.arg(), Atan::new)?
+        } else if let Some(cos) = expr.downcast::<Cos>() {
+            even(expr.clone(), cos.arg(), Cos::new)
+        } else if let Some(sec) = expr.downcast::<Sec>() {
+            even(expr.clone(), sec.arg(), Sec::new)
+        } else if let Some(acos) = expr.downcast::<Acos>() {
+            match extract_minus_sign(&acos.arg().into_expr()) {
+                // acos(-x) = pi - acos(x)
+                Some(arg) => {
+                    let negated = canonical_times([MINUS_ONE.clone() as Expr, Expr::new(Acos::new(arg))])?;
+                    canonical_plus([PI.clone() as Expr, negated])
+                }
+                None => expr,
+            }
+        } else if let Some(sinh) = expr.downcast::<Sinh>() {
+            odd(expr.clone(), sinh.arg(), Sinh::new)?
+        } else if let Some(cosh) = expr.downcast::<Cosh>() {
+            even(expr.clone(), cosh.arg(), Cosh::new)
+        } else if let Some(tanh) = expr.downcast::<Tanh>() {
+            odd(expr.clone(), tanh.arg(), Tanh::new)?
+        } else if let Some(asinh) = expr.downcast::<Asinh>() {
+            odd(expr.clone(), asinh.arg(), Asinh::new)?
+        } else if let Some(atanh) = expr.downcast::<Atanh>() {
+            odd(expr.clone(), atanh.arg(), Atanh::new)?
+        } else {
+            expr.canonical().unwrap_or(expr)
+        })
     }
-}
\ No newline at end of file
+}
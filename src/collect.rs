@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use crate::{Number, canonicalize::canonicalize, expand::{ExpandOptions, expand_with}, expr::Expr, functions::{Plus, Times}, polys::{PolyError, as_power}, symbol::{Symbol, depends_on}};
+
+/// Groups the terms of `expr` by their powers of `syms`, so
+/// `a*x^2 + b*x + c*x^2 + d` in `x` becomes `(a + c)*x^2 + b*x + d`.
+///
+/// `expr` is expanded with respect to `syms` first. Factors that aren't
+/// natural powers of one of `syms`, like `sin(x)` or `1/x`, are kept in
+/// the coefficient.
+pub fn collect(expr: Expr, syms: &[Expr<Symbol>]) -> Expr {
+    let terms = monomials(expr, syms).0.into_iter().map(|(exps, coef)| {
+        let mut factors = vec![coef];
+        for (sym, e) in syms.iter().zip(exps) {
+            if e > 0 {
+                factors.push(sym.clone().pow(e));
+            }
+        }
+        Expr::new(Times::new(factors)) as Expr
+    });
+    canonicalize(Expr::new(Plus::new(terms.collect::<Vec<_>>())))
+}
+
+/// The coefficient of `x^n` in `expr`, so `a*x^2 + b*x` gives `a` for
+/// `n = 2` and zero for `n = 3`.
+///
+/// Fails when `expr` isn't a polynomial in `x`, see [`polynomial`].
+pub fn coeff(expr: Expr, x: Expr<Symbol>, n: u32) -> Result<Expr, PolyError> {
+    Ok(polynomial(expr, &[x])?.remove(&vec![n]).unwrap_or_else(|| Expr::from(0i32)))
+}
+
+/// The highest power of `x` in `expr` with a nonzero coefficient, or
+/// `None` when `expr` is zero.
+///
+/// Fails when `expr` isn't a polynomial in `x`, see [`polynomial`].
+pub fn degree(expr: Expr, x: Expr<Symbol>) -> Result<Option<u32>, PolyError> {
+    Ok(polynomial(expr, &[x])?.keys().next_back().map(|exps| exps[0]))
+}
+
+/// The term of `expr` with the highest power of `x`, as its coefficient
+/// times that power, or zero when `expr` is zero.
+///
+/// Fails when `expr` isn't a polynomial in `x`, see [`polynomial`].
+pub fn leading_term(expr: Expr, x: Expr<Symbol>) -> Result<Expr, PolyError> {
+    Ok(match polynomial(expr, std::slice::from_ref(&x))?.pop_last() {
+        Some((exps, coef)) => canonicalize(coef * x.pow(exps[0])),
+        None => Expr::from(0i32),
+    })
+}
+
+/// The terms of `expr` by their exponents of `syms`, as [`monomials`]
+/// gives them.
+///
+/// Fails when a coefficient has a factor that depends on one of `syms`
+/// without being a natural power of it, like `1/x` or `sin(x)` in `x`,
+/// or that is an infinity.
+fn polynomial(expr: Expr, syms: &[Expr<Symbol>]) -> Result<BTreeMap<Vec<u32>, Expr>, PolyError> {
+    match monomials(expr, syms) {
+        (terms, None) => Ok(terms),
+        (_, Some(factor)) => Err(PolyError::NotPolynomial(factor)),
+    }
+}
+
+/// The terms of `expr` by their exponents of `syms`, leaving out the
+/// ones whose coefficient is zero, with the first factor of a
+/// coefficient that stops `expr` from being a polynomial in `syms`, as
+/// described in [`polynomial`].
+fn monomials(expr: Expr, syms: &[Expr<Symbol>]) -> (BTreeMap<Vec<u32>, Expr>, Option<Expr>) {
+    let expr = expand_with(expr, &ExpandOptions::default().wrt(syms.iter().cloned()).deep(false));
+    let gens: Vec<Expr> = syms.iter().cloned().map(Expr::from).collect();
+    let terms = match expr.downcast::<Plus>() {
+        Some(plus) => plus.terms().to_vec(),
+        None => vec![expr],
+    };
+
+    let mut coefs: BTreeMap<Vec<u32>, Vec<Expr>> = BTreeMap::new();
+    let mut bad = None;
+    for term in terms {
+        let factors = match term.downcast::<Times>() {
+            Some(times) => times.terms().to_vec(),
+            None => vec![term],
+        };
+
+        let mut exps = vec![0; gens.len()];
+        let mut coef = vec![];
+        for factor in factors {
+            let (base, n) = as_power(&factor);
+            match gens.iter().position(|gen| PartialEq::eq(gen, &base)) {
+                Some(i) => exps[i] += n,
+                None => {
+                    let infinite = factor.downcast::<Number>().is_some_and(|n| n.to_rational().is_none());
+                    if bad.is_none() && (infinite || syms.iter().any(|sym| depends_on(factor.rf(), sym))) {
+                        bad = Some(factor.clone());
+                    }
+                    coef.push(factor);
+                }
+            }
+        }
+        coefs.entry(exps).or_default().push(Expr::new(Times::new(coef)));
+    }
+
+    let terms = coefs
+        .into_iter()
+        .map(|(exps, terms)| (exps, canonicalize(Expr::new(Plus::new(terms)))))
+        .filter(|(_, coef)| !coef.downcast::<Number>().is_some_and(Number::is_zero))
+        .collect();
+    (terms, bad)
+}
+
+/// Rewrites the polynomial `expr` in `vars` in nested Horner form, so
+/// `a*x^3 + b*x^2 + c*x + d` becomes `x*(x*(a*x + b) + c) + d`, which
+/// takes fewer multiplications to evaluate and loses less precision.
+///
+/// With several variables, each step factors out the one that occurs
+/// in the most remaining terms, the earliest of `vars` on a tie.
+///
+/// Fails when `expr` isn't a polynomial in `vars`, see [`polynomial`].
+pub fn horner(expr: Expr, vars: &[Expr<Symbol>]) -> Result<Expr, PolyError> {
+    let terms = polynomial(expr, vars)?.into_iter().collect();
+    let vars: Vec<Expr> = vars.iter().cloned().map(Expr::from).collect();
+    Ok(canonicalize(nest(terms, &vars)))
+}
+
+/// `terms` in Horner form, as `v^m * p + q` where `v` is the variable
+/// in the most terms, `v^m` the largest power of it that divides all of
+/// them and `q` the sum of the terms without it.
+fn nest(terms: Vec<(Vec<u32>, Expr)>, vars: &[Expr]) -> Expr {
+    let occurs = |i: usize| terms.iter().filter(|(exps, _)| exps[i] > 0).count();
+    let Some(v) = (0..vars.len()).filter(|&i| occurs(i) > 0).max_by_key(|&i| (occurs(i), std::cmp::Reverse(i))) else {
+        return Expr::new(Plus::new(terms.into_iter().map(|(_, coef)| coef).collect::<Vec<_>>()));
+    };
+
+    let (mut with, without): (Vec<_>, Vec<_>) = terms.into_iter().partition(|(exps, _)| exps[v] > 0);
+    let m = with.iter().map(|(exps, _)| exps[v]).min().unwrap();
+    for (exps, _) in &mut with {
+        exps[v] -= m;
+    }
+
+    let mut nested = Expr::new(Times::new([vars[v].clone().pow(m), nest(with, vars)])) as Expr;
+    if !without.is_empty() {
+        nested = Expr::new(Plus::new([nested, nest(without, vars)]));
+    }
+    nested
+}
@@ -0,0 +1,162 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use num::{BigInt, BigRational, Integer, One, Zero, pow};
+
+use crate::{basic::Basic, expr::{Expr, ExprRef}, visitor::Visitor};
+
+lazy_static::lazy_static! {
+    pub static ref PI: Expr<Constant> = Expr::new(Constant::Pi);
+    pub static ref E: Expr<Constant> = Expr::new(Constant::E);
+    pub static ref I: Expr<Constant> = Expr::new(Constant::I);
+    pub static ref EULER_GAMMA: Expr<Constant> = Expr::new(Constant::EulerGamma);
+}
+
+/// A named mathematical constant that cannot be written exactly as a `Number`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Constant {
+    /// The ratio of a circle's circumference to its diameter.
+    Pi,
+    /// The base of the natural logarithm.
+    E,
+    /// The imaginary unit, `i^2 = -1`.
+    I,
+    /// The Euler–Mascheroni constant.
+    EulerGamma,
+}
+
+/// Extra digits carried through the fixed-point arithmetic so that
+/// rounding in the series does not reach the requested digits.
+const GUARD_DIGITS: u32 = 10;
+
+impl Constant {
+    pub fn is_real(&self) -> bool {
+        !matches!(self, Constant::I)
+    }
+
+    /// Approximates the constant to within `10^-digits`.
+    ///
+    /// Returns `None` for constants that are not real.
+    pub fn approximate(&self, digits: u32) -> Option<BigRational> {
+        let scale = pow(BigInt::from(10), (digits + GUARD_DIGITS) as usize);
+
+        let fixed = match self {
+            Constant::Pi => fixed_pi(&scale),
+            Constant::E => fixed_e(&scale),
+            Constant::EulerGamma => fixed_euler_gamma(&scale, digits + GUARD_DIGITS),
+            Constant::I => return None,
+        };
+
+        Some(BigRational::new(fixed, scale))
+    }
+}
+
+/// `atan(1/n) * scale`, from the Taylor series.
+fn fixed_atan_inv(n: u32, scale: &BigInt) -> BigInt {
+    let n_squared = BigInt::from(n * n);
+    let mut power = scale / n;
+    let mut sum = power.clone();
+    let mut k = 1u32;
+
+    loop {
+        power /= &n_squared;
+        if power.is_zero() {
+            return sum;
+        }
+
+        let term = &power / (2 * k + 1);
+        if k.is_odd() {
+            sum -= term;
+        } else {
+            sum += term;
+        }
+        k += 1;
+    }
+}
+
+/// `pi * scale`, from Machin's formula `pi = 16 atan(1/5) - 4 atan(1/239)`.
+fn fixed_pi(scale: &BigInt) -> BigInt {
+    16 * fixed_atan_inv(5, scale) - 4 * fixed_atan_inv(239, scale)
+}
+
+/// `e * scale`, from the series `sum 1/k!`.
+fn fixed_e(scale: &BigInt) -> BigInt {
+    let mut term = scale.clone();
+    let mut sum = BigInt::zero();
+    let mut k = 1u32;
+
+    while !term.is_zero() {
+        sum += &term;
+        term /= k;
+        k += 1;
+    }
+
+    sum
+}
+
+/// `ln(2) * scale`, from `ln(2) = 2 atanh(1/3)`.
+fn fixed_ln2(scale: &BigInt) -> BigInt {
+    let mut power = scale / 3u32;
+    let mut sum = BigInt::zero();
+    let mut k = 0u32;
+
+    while !power.is_zero() {
+        sum += &power / (2 * k + 1);
+        power /= 9u32;
+        k += 1;
+    }
+
+    2 * sum
+}
+
+/// `gamma * scale`, from the Brent–McMillan algorithm.
+///
+/// With `n = 2^m` the error is about `e^(-4n)`, so `n` is picked
+/// just large enough for the requested number of digits.
+fn fixed_euler_gamma(scale: &BigInt, digits: u32) -> BigInt {
+    // 4n > digits * ln(10)
+    let mut m = 0;
+    while (4u64 << m) as f64 <= digits as f64 * std::f64::consts::LN_10 {
+        m += 1;
+    }
+    let n = BigInt::one() << m;
+    let n_squared = &n * &n;
+
+    let mut a = -(m as i64) * fixed_ln2(scale);
+    let mut b = scale.clone();
+    let mut u = a.clone();
+    let mut v = b.clone();
+    let mut k = BigInt::one();
+
+    loop {
+        b = &b * &n_squared / (&k * &k);
+        a = (&a * &n_squared / &k + &b) / &k;
+
+        if a.is_zero() && b.is_zero() {
+            break;
+        }
+
+        u += &a;
+        v += &b;
+        k += 1;
+    }
+
+    u * scale / v
+}
+
+impl Basic for Constant {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_constant(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        if let Some(other) = other.downcast::<Self>() {
+            self == other
+        } else {
+            false
+        }
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+}
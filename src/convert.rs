@@ -1,6 +1,8 @@
-use std::{fmt, io, ops::ControlFlow};
+use std::{fmt, ops::ControlFlow};
 
-use crate::{Number, expr::{Expr, ExprRef}, functions::{Derivative, Plus, Times}, number::Sign, symbol::Symbol, undefined::Undefined, visitor::Visitor};
+use num::Signed;
+
+use crate::{Number, expr::{Expr, ExprRef}, constant::Constant, functions::{Acos, Acosh, AppliedFunction, Asin, Asinh, Atan, Atan2, Atanh, Cos, Cosh, Cot, Csc, Derivative, Exp, Log, Plus, Pow, Sec, Sin, Sinh, Tan, Tanh, Times}, number::Sign, pattern::Wild, symbol::Symbol, undefined::Undefined, visitor::Visitor};
 
 
 pub trait Convert {
@@ -26,7 +28,7 @@ impl Convert for LaTex {
             res: Ok(())
         };
 
-        expr.rf().visit(&mut visitor);
+        let _ = expr.rf().visit(&mut visitor);
 
         visitor.res
     }
@@ -37,6 +39,43 @@ struct LaTexVisitor<W> {
     res: fmt::Result,
 }
 
+impl<W: fmt::Write> LaTexVisitor<W> {
+    fn emit(&mut self, args: fmt::Arguments) -> ControlFlow<()> {
+        if let Err(e) = self.w.write_fmt(args) {
+            self.res = Err(e);
+            return ControlFlow::Break(());
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Writes `name\\left(args, ...\\right)`.
+    fn visit_call(&mut self, name: &str, args: &[ExprRef]) -> ControlFlow<()> {
+        self.emit(format_args!("{}\\left(", name))?;
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                self.emit(format_args!(", "))?;
+            }
+            arg.visit(self)?;
+        }
+        self.emit(format_args!("\\right)"))
+    }
+
+    /// Writes `expr`, wrapping it in parentheses unless it is atomic.
+    fn visit_grouped(&mut self, expr: ExprRef) -> ControlFlow<()> {
+        let atomic = expr.is::<Symbol>()
+            || matches!(expr.downcast::<Number>(), Some(Number::Integer(i)) if !i.is_negative());
+
+        if atomic {
+            expr.visit(self)
+        } else {
+            self.emit(format_args!("\\left("))?;
+            expr.visit(self)?;
+            self.emit(format_args!("\\right)"))
+        }
+    }
+}
+
 impl<W: fmt::Write> Visitor for LaTexVisitor<W> {
     fn visit_undefined(&mut self, _undef: ExprRef<Undefined>) -> ControlFlow<()> {
         ControlFlow::Break(())
@@ -79,6 +118,19 @@ impl<W: fmt::Write> Visitor for LaTexVisitor<W> {
         ControlFlow::Continue(())
     }
 
+    fn visit_wild(&mut self, wild: ExprRef<Wild>) -> ControlFlow<()> {
+        self.emit(format_args!("{}", wild.as_str()))
+    }
+
+    fn visit_constant(&mut self, c: ExprRef<Constant>) -> ControlFlow<()> {
+        match *c {
+            Constant::Pi => self.emit(format_args!("\\pi")),
+            Constant::E => self.emit(format_args!("e")),
+            Constant::I => self.emit(format_args!("i")),
+            Constant::EulerGamma => self.emit(format_args!("\\gamma")),
+        }
+    }
+
     fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()> {
         for (i, term) in plus.terms().iter().enumerate() {
             term.rf().visit(self)?;
@@ -111,6 +163,113 @@ impl<W: fmt::Write> Visitor for LaTexVisitor<W> {
         ControlFlow::Continue(())
     }
 
+    fn visit_pow(&mut self, pow: ExprRef<Pow>) -> ControlFlow<()> {
+        self.visit_grouped(pow.base())?;
+        self.emit(format_args!("^{{"))?;
+        pow.exp().visit(self)?;
+        self.emit(format_args!("}}"))
+    }
+
+    fn visit_exp(&mut self, exp: ExprRef<Exp>) -> ControlFlow<()> {
+        self.emit(format_args!("e^{{"))?;
+        exp.arg().visit(self)?;
+        self.emit(format_args!("}}"))
+    }
+
+    fn visit_log(&mut self, log: ExprRef<Log>) -> ControlFlow<()> {
+        match log.base() {
+            Some(base) => {
+                self.emit(format_args!("\\log_{{"))?;
+                base.visit(self)?;
+                self.emit(format_args!("}}"))?;
+            }
+            None => self.emit(format_args!("\\ln"))?,
+        }
+
+        self.emit(format_args!("\\left("))?;
+        log.arg().visit(self)?;
+        self.emit(format_args!("\\right)"))
+    }
+
+    fn visit_sin(&mut self, sin: ExprRef<Sin>) -> ControlFlow<()> {
+        self.visit_call("\\sin", &[sin.arg()])
+    }
+
+    fn visit_cos(&mut self, cos: ExprRef<Cos>) -> ControlFlow<()> {
+        self.visit_call("\\cos", &[cos.arg()])
+    }
+
+    fn visit_tan(&mut self, tan: ExprRef<Tan>) -> ControlFlow<()> {
+        self.visit_call("\\tan", &[tan.arg()])
+    }
+
+    fn visit_cot(&mut self, cot: ExprRef<Cot>) -> ControlFlow<()> {
+        self.visit_call("\\cot", &[cot.arg()])
+    }
+
+    fn visit_sec(&mut self, sec: ExprRef<Sec>) -> ControlFlow<()> {
+        self.visit_call("\\sec", &[sec.arg()])
+    }
+
+    fn visit_csc(&mut self, csc: ExprRef<Csc>) -> ControlFlow<()> {
+        self.visit_call("\\csc", &[csc.arg()])
+    }
+
+    fn visit_asin(&mut self, asin: ExprRef<Asin>) -> ControlFlow<()> {
+        self.visit_call("\\arcsin", &[asin.arg()])
+    }
+
+    fn visit_acos(&mut self, acos: ExprRef<Acos>) -> ControlFlow<()> {
+        self.visit_call("\\arccos", &[acos.arg()])
+    }
+
+    fn visit_atan(&mut self, atan: ExprRef<Atan>) -> ControlFlow<()> {
+        self.visit_call("\\arctan", &[atan.arg()])
+    }
+
+    fn visit_atan2(&mut self, atan2: ExprRef<Atan2>) -> ControlFlow<()> {
+        self.visit_call("\\operatorname{atan2}", &[atan2.y(), atan2.x()])
+    }
+
+    fn visit_sinh(&mut self, sinh: ExprRef<Sinh>) -> ControlFlow<()> {
+        self.visit_call("\\sinh", &[sinh.arg()])
+    }
+
+    fn visit_cosh(&mut self, cosh: ExprRef<Cosh>) -> ControlFlow<()> {
+        self.visit_call("\\cosh", &[cosh.arg()])
+    }
+
+    fn visit_tanh(&mut self, tanh: ExprRef<Tanh>) -> ControlFlow<()> {
+        self.visit_call("\\tanh", &[tanh.arg()])
+    }
+
+    fn visit_asinh(&mut self, asinh: ExprRef<Asinh>) -> ControlFlow<()> {
+        self.visit_call("\\operatorname{arsinh}", &[asinh.arg()])
+    }
+
+    fn visit_acosh(&mut self, acosh: ExprRef<Acosh>) -> ControlFlow<()> {
+        self.visit_call("\\operatorname{arcosh}", &[acosh.arg()])
+    }
+
+    fn visit_atanh(&mut self, atanh: ExprRef<Atanh>) -> ControlFlow<()> {
+        self.visit_call("\\operatorname{artanh}", &[atanh.arg()])
+    }
+
+    fn visit_applied_function(&mut self, f: ExprRef<AppliedFunction>) -> ControlFlow<()> {
+        let name = match f.partials() {
+            partials if partials.iter().all(|&n| n == 0) => f.name().to_string(),
+            // f', f'' and f''' for functions of one argument
+            &[n] if n <= 3 => format!("{}{}", f.name(), "'".repeat(n as usize)),
+            partials => {
+                let partials: Vec<String> = partials.iter().map(u32::to_string).collect();
+                format!("{}^{{({})}}", f.name(), partials.join(", "))
+            }
+        };
+
+        let args: Vec<ExprRef> = f.args().iter().map(Expr::rf).collect();
+        self.visit_call(&name, &args)
+    }
+
     fn visit_derivative(&mut self, derivative: ExprRef<Derivative>) -> ControlFlow<()> {
         if let Err(e) = write!(self.w, "\\frac{{d}}{{d{}}}(", derivative.wrt().as_str()) {
             self.res = Err(e);
@@ -126,4 +285,24 @@ impl<W: fmt::Write> Visitor for LaTexVisitor<W> {
 
         ControlFlow::Continue(())
     }
+
+    fn visit_other(&mut self, other: ExprRef) -> ControlFlow<()> {
+        let mut args = vec![];
+        for arg in other.args() {
+            let mut visitor = LaTexVisitor {
+                w: String::new(),
+                res: Ok(()),
+            };
+            if arg.rf().visit(&mut visitor).is_break() {
+                self.res = visitor.res;
+                return ControlFlow::Break(());
+            }
+            args.push(visitor.w);
+        }
+
+        match other.latex(&args) {
+            Some(latex) => self.emit(format_args!("{}", latex)),
+            None => self.emit(format_args!("\\operatorname{{{}}}\\left({}\\right)", other.name(), args.join(", "))),
+        }
+    }
 }
\ No newline at end of file
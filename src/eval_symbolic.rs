@@ -1,63 +1,100 @@
 use std::ops::ControlFlow;
 
-use crate::{Number, canonicalize::canonicalize, expr::{Expr, ExprRef}, functions::{Derivative, Plus, Times}, symbol::Symbol, undefined::{Undefined, UNDEFINED}, visitor::Visitor};
+use crate::{Number, assumptions::is_real, canonicalize::{Canonicalize, canonicalize}, constant::{Constant, E}, expr::Expr, fold::{Transformer, bottom_up}, functions::{Acos, Acosh, Asin, Asinh, Atan, Atan2, Atanh, Cos, Cosh, Cot, Csc, Derivative, Exp, Log, Sec, Sin, Sinh, Tan, Tanh}, number::{ONE, ZERO}, undefined::UNDEFINED};
 
 
 pub fn symbolic_eval(expr: Expr) -> Expr {
-    let mut visitor = SymbolicEvalVisitor {
-        res: UNDEFINED.clone(),
-    };
-
-    match expr.rf().visit(&mut visitor) {
-        ControlFlow::Continue(_) => canonicalize(visitor.res),
+    match bottom_up(expr.rf(), &mut SymbolicEval) {
+        ControlFlow::Continue(res) => res,
         ControlFlow::Break(_) => UNDEFINED.clone(),
     }
 }
 
-struct SymbolicEvalVisitor {
-    res: Expr,
-}
+/// Evaluates a single node whose args are already evaluated, then
+/// canonicalizes the result.
+struct SymbolicEval;
 
-impl Visitor for SymbolicEvalVisitor {
-    fn visit_undefined(&mut self, _undef: ExprRef<Undefined>) -> ControlFlow<()> {
-        self.res = UNDEFINED.clone();
-        ControlFlow::Break(())
-    }
+impl SymbolicEval {
+    fn eval_exp(exp: &Exp) -> Option<Expr> {
+        let arg = exp.arg();
 
-    fn visit_number(&mut self, n: ExprRef<Number>) -> ControlFlow<()> {
-        self.res = n.into_expr();
-        ControlFlow::Continue(())
+        if arg.downcast::<Number>().is_some_and(Number::is_zero) {
+            // e^0 = 1
+            Some(ONE.clone())
+        } else if arg.downcast::<Number>().is_some_and(Number::is_one) {
+            Some(E.clone())
+        } else if let Some(log) = arg.downcast::<Log>().filter(|log| log.is_natural()) {
+            // e^ln(x) = x
+            Some(log.arg().into_expr())
+        } else {
+            None
+        }
     }
 
-    fn visit_symbol(&mut self, sym: ExprRef<Symbol>) -> ControlFlow<()> {
-        self.res = sym.into_expr();
-        ControlFlow::Continue(())
-    }
+    fn eval_log(log: &Log) -> Option<Expr> {
+        let arg = log.arg();
 
-    fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()> {
-        let mut v = vec![];
-        for term in plus.terms() {
-            term.rf().visit(self)?;
-            v.push(self.res.clone());
+        if arg.downcast::<Number>().is_some_and(Number::is_one) {
+            // log(1) = 0
+            Some(ZERO.clone())
+        } else if log.base().is_some_and(|base| PartialEq::eq(&base.into_expr(), &arg.into_expr())) {
+            // log_b(b) = 1
+            Some(ONE.clone())
+        } else if log.is_natural() && arg.downcast::<Constant>() == Some(&Constant::E) {
+            Some(ONE.clone())
+        } else if let Some(exp) = arg.downcast::<Exp>().filter(|exp| log.is_natural() && is_real(exp.arg())) {
+            // ln(e^x) = x, which only holds for real x
+            Some(exp.arg().into_expr())
+        } else {
+            None
         }
-
-        self.res = Expr::new(Plus::new(v));
-        ControlFlow::Continue(())
     }
+}
 
-    fn visit_times(&mut self, times: ExprRef<Times>) -> ControlFlow<()> {
-        let mut v = vec![];
-        for term in times.terms() {
-            term.rf().visit(self)?;
-            v.push(self.res.clone());
-        }
+impl Transformer for SymbolicEval {
+    fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+        let evaluated = if let Some(exp) = expr.downcast::<Exp>() {
+            Self::eval_exp(exp)
+        } else if let Some(log) = expr.downcast::<Log>() {
+            Self::eval_log(log)
+        } else if let Some(sin) = expr.downcast::<Sin>() {
+            sin.eval_exact()
+        } else if let Some(cos) = expr.downcast::<Cos>() {
+            cos.eval_exact()
+        } else if let Some(tan) = expr.downcast::<Tan>() {
+            tan.eval_exact()
+        } else if let Some(cot) = expr.downcast::<Cot>() {
+            cot.eval_exact()
+        } else if let Some(sec) = expr.downcast::<Sec>() {
+            sec.eval_exact()
+        } else if let Some(csc) = expr.downcast::<Csc>() {
+            csc.eval_exact()
+        } else if let Some(asin) = expr.downcast::<Asin>() {
+            asin.eval_exact()
+        } else if let Some(acos) = expr.downcast::<Acos>() {
+            acos.eval_exact()
+        } else if let Some(atan) = expr.downcast::<Atan>() {
+            atan.eval_exact()
+        } else if let Some(atan2) = expr.downcast::<Atan2>() {
+            atan2.eval_exact()
+        } else if let Some(sinh) = expr.downcast::<Sinh>() {
+            sinh.eval_exact()
+        } else if let Some(cosh) = expr.downcast::<Cosh>() {
+            cosh.eval_exact()
+        } else if let Some(tanh) = expr.downcast::<Tanh>() {
+            tanh.eval_exact()
+        } else if let Some(asinh) = expr.downcast::<Asinh>() {
+            asinh.eval_exact()
+        } else if let Some(acosh) = expr.downcast::<Acosh>() {
+            acosh.eval_exact()
+        } else if let Some(atanh) = expr.downcast::<Atanh>() {
+            atanh.eval_exact()
+        } else if let Some(derivative) = expr.downcast::<Derivative>() {
+            Some(canonicalize(derivative.eval()?))
+        } else {
+            expr.eval_symbolic()
+        };
 
-        self.res = Expr::new(Times::new(v));
-        ControlFlow::Continue(())
+        Canonicalize.transform(evaluated.unwrap_or(expr))
     }
-
-    fn visit_derivative(&mut self, derivative: ExprRef<Derivative>) -> ControlFlow<()> {
-        self.res = derivative.eval()?;
-        ControlFlow::Continue(())
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,236 @@
+use std::ops::ControlFlow;
+
+use num::{BigInt, One, ToPrimitive};
+
+use crate::{Number, basic::Basic, canonicalize::{Canonicalize, canonicalize}, expr::{Expr, ExprRef}, fold::{Fold, Transformer, fold, rebuild}, functions::{Plus, Pow, Times}, simplify::collect_like_terms, symbol::{Symbol, depends_on}, undefined::{Undefined, UNDEFINED}};
+
+/// Controls what [`expand_with`] expands.
+#[derive(Debug, Clone)]
+pub struct ExpandOptions {
+    wrt: Vec<Expr<Symbol>>,
+    deep: bool,
+}
+
+impl Default for ExpandOptions {
+    fn default() -> Self {
+        Self {
+            wrt: vec![],
+            deep: true,
+        }
+    }
+}
+
+impl ExpandOptions {
+    /// Only expands with respect to `symbols`. Terms of a sum that don't
+    /// depend on any of them are kept together as a single coefficient,
+    /// so `(a + b + x) * x` expands to `(a + b) * x + x^2`.
+    pub fn wrt(mut self, symbols: impl IntoIterator<Item = Expr<Symbol>>) -> Self {
+        self.wrt = symbols.into_iter().collect();
+        self
+    }
+
+    /// Whether to expand inside the args of functions such as `sin`,
+    /// which is the default.
+    pub fn deep(mut self, deep: bool) -> Self {
+        self.deep = deep;
+        self
+    }
+}
+
+/// Distributes products over sums and expands integer powers of sums,
+/// so `(x + 1)^2 * y` becomes `x^2 * y + 2 * x * y + y`.
+pub fn expand(expr: Expr) -> Expr {
+    expand_with(expr, &ExpandOptions::default())
+}
+
+/// See [`expand`] and [`ExpandOptions`].
+pub fn expand_with(expr: Expr, options: &ExpandOptions) -> Expr {
+    match fold(canonicalize(expr).rf(), &mut Expander { options }) {
+        ControlFlow::Continue(res) => res,
+        ControlFlow::Break(_) => UNDEFINED.clone(),
+    }
+}
+
+/// Expands each node given its args already expanded.
+struct Expander<'a> {
+    options: &'a ExpandOptions,
+}
+
+impl Expander<'_> {
+    /// Whether `expr` is a constant as far as expanding is concerned.
+    fn independent(&self, expr: &Expr) -> bool {
+        !self.options.wrt.is_empty() && !self.options.wrt.iter().any(|sym| depends_on(expr.rf(), sym))
+    }
+
+    /// The terms that `expr` is distributed as.
+    fn terms(&self, expr: &Expr) -> Vec<Expr> {
+        let Some(plus) = expr.downcast::<Plus>() else {
+            return vec![expr.clone()];
+        };
+
+        let (independent, mut terms): (Vec<Expr>, Vec<Expr>) = plus.terms().iter().cloned().partition(|t| self.independent(t));
+        if terms.is_empty() {
+            return vec![expr.clone()];
+        }
+        if !independent.is_empty() {
+            terms.push(canonicalize(Expr::new(Plus::new(independent))));
+        }
+        terms
+    }
+
+    /// Adds up `terms`, collecting the ones that are alike apart from a
+    /// coefficient, which with `wrt` is any factor that is independent.
+    fn sum(&self, terms: Vec<Expr>) -> Expr {
+        let sum = collect_like_terms(Expr::new(Plus::new(terms)));
+        let Some(plus) = sum.downcast::<Plus>().filter(|_| !self.options.wrt.is_empty()) else {
+            return sum;
+        };
+
+        let mut like: Vec<(Expr, Vec<Expr>)> = vec![];
+        for term in plus.terms() {
+            let factors = match term.downcast::<Times>() {
+                Some(times) => times.terms().to_vec(),
+                None => vec![term.clone()],
+            };
+            let (coef, rest): (Vec<Expr>, Vec<Expr>) = factors.into_iter().partition(|f| self.independent(f));
+            let rest = canonicalize(Expr::new(Times::new(rest)));
+            let coef = canonicalize(Expr::new(Times::new(coef)));
+
+            match like.iter_mut().find(|(other, _)| PartialEq::eq(other, &rest)) {
+                Some((_, coefs)) => coefs.push(coef),
+                None => like.push((rest, vec![coef])),
+            }
+        }
+
+        let terms = like.into_iter().map(|(rest, coefs)| Expr::new(Times::new([Expr::new(Plus::new(coefs)) as Expr, rest])) as Expr);
+        canonicalize(Expr::new(Plus::new(terms.collect::<Vec<_>>())))
+    }
+
+    /// Multiplies out `factors`, which are already expanded.
+    fn product(&self, factors: impl IntoIterator<Item = Expr>) -> Expr {
+        let mut products: Vec<Vec<Expr>> = vec![vec![]];
+        for factor in factors {
+            let terms = self.terms(&factor);
+            products = products
+                .into_iter()
+                .flat_map(|product| terms.iter().map(move |term| {
+                    let mut product = product.clone();
+                    product.push(term.clone());
+                    product
+                }))
+                .collect();
+        }
+
+        let terms = products.into_iter().map(|factors| canonicalize(Expr::new(Times::new(factors))));
+        self.sum(terms.collect())
+    }
+
+    /// Expands `expr` if it is an integer power, whose base is already
+    /// expanded, and returns it as it is otherwise.
+    fn integer_power(&self, expr: Expr) -> Expr {
+        let Some(pow) = expr.downcast::<Pow>() else {
+            return expr;
+        };
+        let n = match pow.exp().downcast::<Number>() {
+            Some(Number::Integer(n)) => n.to_i64(),
+            _ => None,
+        };
+
+        match n.and_then(|n| Some((n.unsigned_abs().to_u32()?, n < 0))) {
+            Some((n, false)) => self.power(pow.base().into_expr(), n),
+            // 1 / (x + 1)^2 = 1 / (x^2 + 2 * x + 1)
+            Some((n, true)) if n > 1 => canonicalize(self.power(pow.base().into_expr(), n).pow(-1)),
+            _ => expr,
+        }
+    }
+
+    /// Expands `base^n`, where `base` is already expanded.
+    fn power(&self, base: Expr, n: u32) -> Expr {
+        if let Some(times) = base.downcast::<Times>() {
+            // (a * b)^n = a^n * b^n
+            return self.product(times.terms().iter().map(|factor| self.power(factor.clone(), n)));
+        }
+
+        let terms = self.terms(&base);
+        if terms.len() < 2 {
+            return canonicalize(base.pow(n));
+        }
+
+        // (t_1 + ... + t_m)^n = sum of n! / (k_1! ... k_m!) * t_1^k_1 * ... * t_m^k_m
+        // over every k_1 + ... + k_m = n
+        let mut sum = vec![];
+        for ks in compositions(n, terms.len()) {
+            let coef = ks.iter().fold(factorial(n), |coef, &k| coef / factorial(k));
+            let mut factors = vec![Expr::new(Number::Integer(coef)) as Expr];
+            for (term, &k) in terms.iter().zip(&ks) {
+                if k > 0 {
+                    factors.push(self.power(term.clone(), k));
+                }
+            }
+            sum.push(self.product(factors));
+        }
+
+        self.sum(sum)
+    }
+}
+
+impl Fold for Expander<'_> {
+    type Output = Expr;
+
+    fn fold_pre(&mut self, expr: ExprRef) -> ControlFlow<(), Option<Expr>> {
+        if expr.is::<Undefined>() {
+            return ControlFlow::Break(());
+        }
+
+        let algebraic = expr.is::<Plus>() || expr.is::<Times>() || expr.is::<Pow>();
+        ControlFlow::Continue((!self.options.deep && !algebraic).then(|| expr.into_expr()))
+    }
+
+    fn fold(&mut self, expr: ExprRef, args: Vec<Expr>) -> ControlFlow<(), Expr> {
+        let expr = Canonicalize.transform(rebuild(expr, args))?;
+
+        ControlFlow::Continue(if let Some(plus) = expr.downcast::<Plus>() {
+            self.sum(plus.terms().to_vec())
+        } else if let Some(times) = expr.downcast::<Times>() {
+            // canonicalizing merges equal factors into powers, so those
+            // are expanded too
+            self.product(times.terms().iter().map(|factor| self.integer_power(factor.clone())))
+        } else {
+            self.integer_power(expr)
+        })
+    }
+}
+
+fn factorial(n: u32) -> BigInt {
+    (1..=n).fold(BigInt::one(), |acc, k| acc * k)
+}
+
+/// Every way of writing `n` as an ordered sum of `parts` naturals.
+fn compositions(n: u32, parts: usize) -> Vec<Vec<u32>> {
+    if parts == 1 {
+        return vec![vec![n]];
+    }
+
+    let mut res = vec![];
+    for k in 0..=n {
+        for mut rest in compositions(n - k, parts - 1) {
+            rest.insert(0, k);
+            res.push(rest);
+        }
+    }
+    res
+}
+
+impl<A: Basic> Expr<A> {
+    /// See [`expand`].
+    pub fn expand(&self) -> Expr {
+        expand(self.clone().into())
+    }
+}
+
+impl Expr {
+    /// See [`expand`].
+    pub fn expand(&self) -> Expr {
+        expand(self.clone())
+    }
+}
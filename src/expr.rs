@@ -33,7 +33,7 @@ impl<T: Basic> Expr<T> {
 
 impl<T: ?Sized> Expr<T> {
     /// Stands for "ref"
-    pub(crate) fn rf(&self) -> ExprRef<T> {
+    pub fn rf(&self) -> ExprRef<'_, T> {
         ExprRef {
             ptr: self.ptr,
             _marker: PhantomData,
@@ -118,13 +118,33 @@ impl<T: Basic> From<Expr<T>> for Expr {
     }
 }
 
-impl<T: ?Sized + Basic + PartialEq> PartialEq for Expr<T> {
-    fn eq(&self, other: &Self) -> bool {
-        <T as PartialEq>::eq(&*self, &*other)
+impl<T: Basic> From<&'_ Expr<T>> for Expr {
+    fn from(e: &Expr<T>) -> Self {
+        e.clone()
+    }
+}
+
+impl From<&'_ Expr> for Expr {
+    fn from(e: &Expr) -> Self {
+        e.clone()
     }
+}
 
-    fn ne(&self, other: &Self) -> bool {
-        T::ne(&*self, &*other)
+impl<T: Basic> From<ExprRef<'_, T>> for Expr {
+    fn from(e: ExprRef<T>) -> Self {
+        e.into_expr()
+    }
+}
+
+impl From<ExprRef<'_>> for Expr {
+    fn from(e: ExprRef) -> Self {
+        e.into_expr()
+    }
+}
+
+impl<T: ?Sized + Basic + PartialEq> PartialEq for Expr<T> {
+    fn eq(&self, other: &Self) -> bool {
+        <T as PartialEq>::eq(self, other)
     }
 }
 
@@ -132,35 +152,35 @@ impl<T: ?Sized + Basic + Eq> Eq for Expr<T> {}
 
 impl<T: ?Sized + Basic + PartialOrd> PartialOrd for Expr<T> {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        T::partial_cmp(&*self, &*other)
+        T::partial_cmp(self, other)
     }
 
     fn lt(&self, other: &Self) -> bool {
-        T::lt(&*self, &*other)
+        T::lt(self, other)
     }
 
     fn le(&self, other: &Self) -> bool {
-        T::le(&*self, &*other)
+        T::le(self, other)
     }
 
     fn gt(&self, other: &Self) -> bool {
-        T::gt(&*self, &*other)
+        T::gt(self, other)
     }
 
     fn ge(&self, other: &Self) -> bool {
-        T::ge(&*self, &*other)
+        T::ge(self, other)
     }
 }
 
 impl<T: ?Sized + Basic + Ord> Ord for Expr<T> {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
-        <T as Ord>::cmp(&*self, &*other)
+        <T as Ord>::cmp(self, other)
     }
 }
 
 impl<T: ?Sized + Basic + Debug> Debug for Expr<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        <T as Debug>::fmt(&*self, f)
+        <T as Debug>::fmt(self, f)
     }
 }
 
@@ -184,7 +204,7 @@ impl<T: ?Sized> ExprRef<'_, T> {
 
 impl<T: ?Sized> Clone for ExprRef<'_, T> {
     fn clone(&self) -> Self {
-        Self { ..*self }
+        *self
     }
 }
 
@@ -0,0 +1,896 @@
+use std::collections::BTreeMap;
+
+use num::{BigInt, BigRational, BigUint, Integer, One, Signed, ToPrimitive, Zero};
+
+use crate::{Number, canonicalize::canonicalize, expr::Expr, functions::{Plus, Pow, Times}, polys::Poly};
+
+/// Factors `expr` into polynomials that are irreducible over the
+/// rationals, as a `Times` of the factors raised to their multiplicities,
+/// so `2*x^3 - 2*x` becomes `2 * x * (x - 1) * (x + 1)`.
+///
+/// Anything that isn't a sum, product or integer power, such as `sin(x)`,
+/// is treated as one more variable. Products and powers are factored one
+/// factor at a time, so denominators are factored too.
+pub fn factor(expr: Expr) -> Expr {
+    let expr = canonicalize(expr);
+
+    if let Some(times) = expr.downcast::<Times>() {
+        let factors: Vec<Expr> = times.terms().iter().cloned().map(factor).collect();
+        return canonicalize(Expr::new(Times::new(factors)));
+    }
+
+    if let Some(pow) = expr.downcast::<Pow>() {
+        if pow.exp().downcast::<Number>().is_some_and(Number::is_integer) {
+            return canonicalize(factor(pow.base().into_expr()).pow(pow.exp()));
+        }
+    }
+
+    if !expr.is::<Plus>() {
+        return expr;
+    }
+
+    let (coef, factors) = factor_list(expr);
+    let mut product: Vec<Expr> = vec![Expr::new(coef)];
+    for (f, m) in factors {
+        product.push(if m == 1 { f } else { Expr::new(Pow::new(f, Expr::from(m))) });
+    }
+    canonicalize(Expr::new(Times::new(product)))
+}
+
+/// Factors the polynomial `expr` into a number and irreducible factors
+/// with their multiplicities. Each factor has integer coefficients with
+/// no common divisor and a positive leading coefficient.
+pub fn factor_list(expr: Expr) -> (Number, Vec<(Expr, u32)>) {
+    let poly = match Poly::from_expr(&expr, &[]) {
+        Ok(poly) => poly,
+        // an infinity, which is left alone
+        Err(_) => return (Number::Integer(BigInt::one()), vec![(canonicalize(expr), 1)]),
+    };
+    if poly.is_zero() {
+        return (Number::Integer(BigInt::zero()), vec![]);
+    }
+    let terms: BTreeMap<Vec<u32>, BigRational> = poly
+        .terms()
+        .into_iter()
+        .map(|(e, c)| (e.to_vec(), c.to_rational().unwrap()))
+        .collect();
+
+    // clear denominators, then take out the content with the sign of
+    // the leading coefficient
+    let denom = terms.values().fold(BigInt::one(), |l, c| l.lcm(c.denom()));
+    let ints: MPoly = terms.into_iter().map(|(e, c)| (e, (c * &denom).to_integer())).collect();
+    let mut content = ints.values().fold(BigInt::zero(), |g, c| g.gcd(c));
+    if ints.values().next_back().unwrap().is_negative() {
+        content = -content;
+    }
+    let primitive: MPoly = ints.into_iter().map(|(e, c)| (e, c / &content)).collect();
+
+    let coef = Number::from_rational(BigRational::new(content, denom));
+    let factors = factor_primitive(primitive, poly.gens())
+        .into_iter()
+        .map(|(f, m)| {
+            let terms = f.into_iter().map(|(e, c)| (e, Number::Integer(c)));
+            (Poly::from_terms(poly.gens().to_vec(), terms).to_expr(), m)
+        })
+        .collect();
+    (coef, factors)
+}
+
+/// A polynomial in several variables with integer coefficients, keyed by
+/// exponent vectors. The last entry is the leading term in lex order.
+type MPoly = BTreeMap<Vec<u32>, BigInt>;
+
+/// Factors a primitive polynomial with a positive leading coefficient
+/// in `gens`.
+fn factor_primitive(mut f: MPoly, gens: &[Expr]) -> Vec<(MPoly, u32)> {
+    let n = gens.len();
+    let mut res = vec![];
+
+    // take out the largest monomial dividing every term
+    let lowest: Vec<u32> = (0..n).map(|i| f.keys().map(|e| e[i]).min().unwrap()).collect();
+    for (i, &k) in lowest.iter().enumerate() {
+        if k > 0 {
+            let mut var = vec![0; n];
+            var[i] = 1;
+            res.push((MPoly::from([(var, BigInt::one())]), k));
+        }
+    }
+    f = f.into_iter().map(|(e, c)| (e.iter().zip(&lowest).map(|(a, b)| a - b).collect(), c)).collect();
+
+    let used: Vec<usize> = (0..n).filter(|&i| f.keys().any(|e| e[i] > 0)).collect();
+    match used[..] {
+        [] => {}
+        [var] => {
+            // a polynomial in one variable
+            let mut dense = vec![BigInt::zero(); f.keys().map(|e| e[var] as usize).max().unwrap() + 1];
+            for (e, c) in &f {
+                dense[e[var] as usize] = c.clone();
+            }
+
+            let (_, factors) = factor_univariate(&dense);
+            for (g, m) in factors {
+                let g = g.into_iter().enumerate().filter(|(_, c)| !c.is_zero()).map(|(k, c)| {
+                    let mut e = vec![0; n];
+                    e[var] = k as u32;
+                    (e, c)
+                });
+                res.push((g.collect(), m));
+            }
+        }
+        _ => res.extend(factor_several(f, gens)),
+    }
+
+    res
+}
+
+/// Factors a primitive polynomial in several of `gens`, as a polynomial
+/// in the variable `x` of lowest degree with coefficients in the others.
+/// Its content in `x` is factored on its own, and what is left is split
+/// into square-free parts for [`factor_square_free`].
+fn factor_several(f: MPoly, gens: &[Expr]) -> Vec<(MPoly, u32)> {
+    let f = to_poly(&f, gens);
+    let x = (0..gens.len()).filter(|&i| f.degree(i) > Some(0)).min_by_key(|&i| f.degree(i)).unwrap();
+
+    let content = content_in(&f, x);
+    let mut res = factor_primitive(from_poly(&content), gens);
+    for (g, m) in square_free_in(&f.div_exact(&content).unwrap(), x) {
+        res.extend(factor_square_free(&g.primitive().1, x).iter().map(|h| (from_poly(h), m)));
+    }
+    res
+}
+
+/// Factors `f`, which is square-free and primitive in `x`, into
+/// irreducibles.
+///
+/// The other variables `y` are set to integers `a` that keep the degree
+/// of `f` in `x` and its square-freeness, and the image in `x` is
+/// factored. Its factors are lifted to monic factors of `f` over power
+/// series in `y - a`, up to the total degree of `f` in `y`, and products
+/// of them are tried like in [`zassenhaus`].
+fn factor_square_free(f: &Poly, x: usize) -> Vec<Poly> {
+    if f.degree(x) == Some(1) {
+        return vec![f.clone()];
+    }
+    let (point, images) = evaluation_point(f, x);
+    if images.len() == 1 {
+        return vec![f.clone()];
+    }
+
+    // move the point to y = 0
+    let g = shift(f, x, &point);
+    let d = g.terms().iter().map(|(e, _)| y_degree(e, x)).max().unwrap();
+    let lc = g.coeff_in(x, g.degree(x).unwrap());
+    let monic = truncate(&(&g * &inverse(&lc, x, d)), x, d);
+    let images = images
+        .iter()
+        .map(|u| {
+            let terms = u.iter().enumerate().map(|(k, c)| {
+                let mut e = vec![0; g.gens().len()];
+                e[x] = k as u32;
+                (e, Number::Integer(c.clone()))
+            });
+            Poly::from_terms(g.gens().to_vec(), terms).monic()
+        })
+        .collect();
+    let mut lifted = hensel_lift_in(&monic, x, images, d);
+
+    // a factor h of f is lc(f / h) times the product of its lifted
+    // factors, which is a polynomial of degree at most d in y
+    let back: Vec<BigInt> = point.iter().map(|a| -a).collect();
+    let mut res = vec![];
+    let mut rest = g;
+    let mut size = 1;
+    'sizes: while 2 * size <= lifted.len() {
+        for subset in combinations(lifted.len(), size) {
+            let lc = rest.coeff_in(x, rest.degree(x).unwrap());
+            let product = subset.iter().fold(lc, |p, &i| truncate(&(p * &lifted[i]), x, d));
+            let candidate = product.div_exact(&content_in(&product, x)).unwrap().primitive().1;
+            let Some(q) = rest.div_exact(&candidate) else {
+                continue;
+            };
+
+            res.push(shift(&candidate, x, &back).primitive().1);
+            rest = q;
+            for &i in subset.iter().rev() {
+                lifted.remove(i);
+            }
+            continue 'sizes;
+        }
+        size += 1;
+    }
+
+    res.push(shift(&rest, x, &back).primitive().1);
+    res
+}
+
+/// Integers to set every variable but `x` to, with the irreducible
+/// factors of the image of `f` there. The image has to keep the degree
+/// of `f` and be square-free. Of the first few such points, the one
+/// with the fewest factors is taken, to leave less to recombine.
+fn evaluation_point(f: &Poly, x: usize) -> (Vec<BigInt>, Vec<Vec<BigInt>>) {
+    let mut rng = 0x2545_f491_4f6c_dd1d_u64;
+    let mut best: Option<(Vec<BigInt>, Vec<Vec<BigInt>>)> = None;
+    let mut found = 0;
+    for attempt in 0u64.. {
+        // zero first, since it keeps f sparse, then points further out
+        let width = 2 * (attempt / 8) + 3;
+        let point: Vec<BigInt> = (0..f.gens().len())
+            .map(|i| {
+                if attempt == 0 || i == x {
+                    return BigInt::zero();
+                }
+                rng ^= rng << 13;
+                rng ^= rng >> 7;
+                rng ^= rng << 17;
+                BigInt::from((rng % width) as i64 - (width / 2) as i64)
+            })
+            .collect();
+
+        let mut image = vec![BigInt::zero(); f.degree(x).unwrap() as usize + 1];
+        for (e, c) in f.terms() {
+            let c = c.to_rational().unwrap().to_integer();
+            let value = e.iter().enumerate().filter(|&(i, _)| i != x).fold(c, |v, (i, &k)| v * point[i].pow(k));
+            image[e[x] as usize] += value;
+        }
+        if image.last().unwrap().is_zero() || z_gcd(&image, &z_derivative(&image)).len() > 1 {
+            continue;
+        }
+
+        let (_, factors) = factor_univariate(&image);
+        let factors: Vec<Vec<BigInt>> = factors.into_iter().map(|(g, _)| g).collect();
+        if best.as_ref().is_none_or(|(_, best)| factors.len() < best.len()) {
+            best = Some((point, factors));
+        }
+        found += 1;
+        if found == 3 || best.as_ref().is_some_and(|(_, best)| best.len() == 1) {
+            break;
+        }
+    }
+    best.unwrap()
+}
+
+/// Lifts the monic factors `u` of `f` at `y = 0`, where `y` are the
+/// variables other than `x`, to monic factors of `f` over power series
+/// in `y`, up to degree `d`.
+///
+/// The error `f - g_1 * ... * g_r` of degree `k` in `y` is a sum of
+/// `c(x) * y^m`, and each `g_i` is corrected by `s_i * c mod u_i` times
+/// `y^m`, where the sum of each `s_i` times the other `u_j` is one.
+fn hensel_lift_in(f: &Poly, x: usize, u: Vec<Poly>, d: u32) -> Vec<Poly> {
+    let gens = f.gens().to_vec();
+    let one = Poly::constant(gens.clone(), Number::Integer(BigInt::one()));
+    let s: Vec<Poly> = (0..u.len())
+        .map(|i| {
+            let others = u.iter().enumerate().filter(|&(j, _)| j != i).fold(one.clone(), |p, (_, g)| p * g);
+            u[i].bezout(&others).1
+        })
+        .collect();
+
+    let mut g = u.clone();
+    for _ in 0..d {
+        let product = g.iter().fold(one.clone(), |p, h| truncate(&(p * h), x, d));
+        let error = f - &product;
+        let Some(k) = error.terms().iter().map(|(e, _)| y_degree(e, x)).min() else {
+            break;
+        };
+
+        let mut parts: BTreeMap<Vec<u32>, Vec<(Vec<u32>, Number)>> = BTreeMap::new();
+        for (e, c) in error.terms().into_iter().filter(|(e, _)| y_degree(e, x) == k) {
+            let mut m = e.to_vec();
+            m[x] = 0;
+            let mut power = vec![0; gens.len()];
+            power[x] = e[x];
+            parts.entry(m).or_default().push((power, c.clone()));
+        }
+        for (m, c) in parts {
+            let c = Poly::from_terms(gens.clone(), c);
+            let monomial = Poly::from_terms(gens.clone(), [(m, Number::Integer(BigInt::one()))]);
+            for i in 0..g.len() {
+                let correction = (&c * &s[i]).div_rem(&u[i]).1;
+                g[i] = &g[i] + &(correction * &monomial);
+            }
+        }
+    }
+    g
+}
+
+/// The inverse of `p`, which has no `x` and a nonzero constant term, as
+/// a power series in the other variables up to degree `d`, by Newton's
+/// iteration.
+fn inverse(p: &Poly, x: usize, d: u32) -> Poly {
+    let gens = p.gens().to_vec();
+    let c = p.coeff(&vec![0; gens.len()]).to_rational().unwrap();
+    let two = Poly::constant(gens.clone(), Number::Integer(BigInt::from(2)));
+    let mut inv = Poly::constant(gens, Number::from_rational(c.recip()));
+    let mut precision = 1;
+    while precision <= d {
+        precision = (2 * precision).min(d + 1);
+        let error = &two - &truncate(&(p * &inv), x, precision - 1);
+        inv = truncate(&(inv * error), x, precision - 1);
+    }
+    inv
+}
+
+/// The terms of `f` of degree at most `d` in the variables other than `x`.
+fn truncate(f: &Poly, x: usize, d: u32) -> Poly {
+    let terms = f.terms().into_iter().filter(|(e, _)| y_degree(e, x) <= d).map(|(e, c)| (e.to_vec(), c.clone()));
+    Poly::from_terms(f.gens().to_vec(), terms)
+}
+
+fn y_degree(e: &[u32], x: usize) -> u32 {
+    e.iter().sum::<u32>() - e[x]
+}
+
+/// `f` with each variable `y` other than `x` replaced by `y + a`, for
+/// the `a` at its index in `point`.
+fn shift(f: &Poly, x: usize, point: &[BigInt]) -> Poly {
+    let gens = f.gens().to_vec();
+    let mut f = f.clone();
+    for (i, a) in point.iter().enumerate().filter(|&(i, a)| i != x && !a.is_zero()) {
+        let Some(degree) = f.degree(i) else {
+            continue;
+        };
+        let y = Poly::gen(gens.clone(), i) + Poly::constant(gens.clone(), Number::Integer(a.clone()));
+        let mut power = Poly::constant(gens.clone(), Number::Integer(BigInt::one()));
+        let mut shifted = Poly::zero(gens.clone());
+        for k in 0..=degree {
+            shifted = shifted + f.coeff_in(i, k) * &power;
+            power = power * &y;
+        }
+        f = shifted;
+    }
+    f
+}
+
+/// The gcd of the coefficients of `f` as a polynomial in `x`.
+fn content_in(f: &Poly, x: usize) -> Poly {
+    (0..=f.degree(x).unwrap()).fold(Poly::zero(f.gens().to_vec()), |g, k| g.gcd(&f.coeff_in(x, k)))
+}
+
+/// Yun's square-free decomposition of `f`, primitive in `x`, as `(g, m)`
+/// pairs where `f` is the product of each `g^m` up to a number.
+fn square_free_in(f: &Poly, x: usize) -> Vec<(Poly, u32)> {
+    let df = f.derivative(x);
+    let a = f.gcd(&df);
+    let mut b = f.div_exact(&a).unwrap();
+    let c = df.div_exact(&a).unwrap();
+    let mut d = c - b.derivative(x);
+
+    let mut res = vec![];
+    let mut m = 1;
+    while b.degree(x) > Some(0) {
+        let a = b.gcd(&d);
+        if a.degree(x) > Some(0) {
+            res.push((a.clone(), m));
+        }
+        let c = d.div_exact(&a).unwrap();
+        b = b.div_exact(&a).unwrap();
+        d = c - b.derivative(x);
+        m += 1;
+    }
+    res
+}
+
+fn to_poly(f: &MPoly, gens: &[Expr]) -> Poly {
+    Poly::from_terms(gens.to_vec(), f.iter().map(|(e, c)| (e.clone(), Number::Integer(c.clone()))))
+}
+
+/// A polynomial with integer coefficients, such as one from
+/// [`Poly::primitive`], as an [`MPoly`].
+fn from_poly(f: &Poly) -> MPoly {
+    f.terms().into_iter().map(|(e, c)| (e.to_vec(), c.to_rational().unwrap().to_integer())).collect()
+}
+
+/// Every `k` element subset of `0..n`, in lexicographic order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    fn go(start: usize, n: usize, k: usize, current: &mut Vec<usize>, res: &mut Vec<Vec<usize>>) {
+        if current.len() == k {
+            res.push(current.clone());
+            return;
+        }
+        for i in start..n {
+            current.push(i);
+            go(i + 1, n, k, current, res);
+            current.pop();
+        }
+    }
+
+    let mut res = vec![];
+    go(0, n, k, &mut vec![], &mut res);
+    res
+}
+
+// Polynomials in one variable are dense vectors of coefficients, lowest
+// degree first, without trailing zeros.
+
+fn z_trim(mut f: Vec<BigInt>) -> Vec<BigInt> {
+    while f.last().is_some_and(Zero::is_zero) {
+        f.pop();
+    }
+    f
+}
+
+fn z_sub(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let mut res = vec![BigInt::zero(); a.len().max(b.len())];
+    for (i, c) in a.iter().enumerate() {
+        res[i] += c;
+    }
+    for (i, c) in b.iter().enumerate() {
+        res[i] -= c;
+    }
+    z_trim(res)
+}
+
+fn z_mul(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut res = vec![BigInt::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            res[i + j] += x * y;
+        }
+    }
+    z_trim(res)
+}
+
+fn z_derivative(f: &[BigInt]) -> Vec<BigInt> {
+    z_trim(f.iter().enumerate().skip(1).map(|(i, c)| c * i).collect())
+}
+
+fn z_content(f: &[BigInt]) -> BigInt {
+    f.iter().fold(BigInt::zero(), |g, c| g.gcd(c))
+}
+
+/// `f` divided by its content, with a positive leading coefficient.
+fn z_primitive(f: &[BigInt]) -> Vec<BigInt> {
+    let mut content = z_content(f);
+    if f.last().is_some_and(Signed::is_negative) {
+        content = -content;
+    }
+    if content.is_zero() {
+        return vec![];
+    }
+    f.iter().map(|c| c / &content).collect()
+}
+
+/// `a / b`, if `b` divides `a` with an integer quotient.
+fn z_div_exact(a: &[BigInt], b: &[BigInt]) -> Option<Vec<BigInt>> {
+    let lc = b.last()?;
+    if a.len() < b.len() {
+        return a.is_empty().then(Vec::new);
+    }
+
+    let mut rem = a.to_vec();
+    let mut quot = vec![BigInt::zero(); a.len() - b.len() + 1];
+    for i in (0..quot.len()).rev() {
+        let (q, r) = rem[i + b.len() - 1].div_rem(lc);
+        if !r.is_zero() {
+            return None;
+        }
+        for (j, c) in b.iter().enumerate() {
+            rem[i + j] -= &q * c;
+        }
+        quot[i] = q;
+    }
+
+    z_trim(rem).is_empty().then(|| z_trim(quot))
+}
+
+/// The pseudo-remainder of `a` by `b`, `lc(b)^(deg a - deg b + 1) * a mod b`.
+fn z_prem(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let lc = b.last().unwrap();
+    let mut rem = a.to_vec();
+    while rem.len() >= b.len() {
+        let shift = rem.len() - b.len();
+        let lead = rem.last().unwrap().clone();
+        rem.iter_mut().for_each(|c| *c *= lc);
+        for (j, c) in b.iter().enumerate() {
+            rem[shift + j] -= &lead * c;
+        }
+        rem = z_trim(rem);
+    }
+    rem
+}
+
+/// The greatest common divisor of `a` and `b`, by the primitive PRS.
+fn z_gcd(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let (mut a, mut b) = (z_primitive(a), z_primitive(b));
+    while !b.is_empty() {
+        let r = z_primitive(&z_prem(&a, &b));
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// Factors `f` into its content, with the sign of its leading
+/// coefficient, and irreducible primitive factors with multiplicities.
+fn factor_univariate(f: &[BigInt]) -> (BigInt, Vec<(Vec<BigInt>, u32)>) {
+    let mut content = z_content(f);
+    if f.last().is_some_and(Signed::is_negative) {
+        content = -content;
+    }
+
+    let mut res = vec![];
+    let zeros = f.iter().take_while(|c| c.is_zero()).count();
+    if zeros > 0 {
+        res.push((vec![BigInt::zero(), BigInt::one()], zeros as u32));
+    }
+
+    let f: Vec<BigInt> = f[zeros..].iter().map(|c| c / &content).collect();
+    for (g, m) in square_free(&f) {
+        res.extend(zassenhaus(&g).into_iter().map(|h| (h, m)));
+    }
+    (content, res)
+}
+
+/// Yun's square-free decomposition of a primitive `f`, as `(g, m)` pairs
+/// where `f` is the product of each `g^m`.
+fn square_free(f: &[BigInt]) -> Vec<(Vec<BigInt>, u32)> {
+    if f.len() < 2 {
+        return vec![];
+    }
+
+    let df = z_derivative(f);
+    let a = z_gcd(f, &df);
+    let mut b = z_div_exact(f, &a).unwrap();
+    let c = z_div_exact(&df, &a).unwrap();
+    let mut d = z_sub(&c, &z_derivative(&b));
+
+    let mut res = vec![];
+    let mut m = 1;
+    while b.len() > 1 {
+        let a = z_gcd(&b, &d);
+        if a.len() > 1 {
+            res.push((a.clone(), m));
+        }
+        let c = z_div_exact(&d, &a).unwrap();
+        b = z_div_exact(&b, &a).unwrap();
+        d = z_sub(&c, &z_derivative(&b));
+        m += 1;
+    }
+    res
+}
+
+/// Factors a primitive, square-free `f` with a positive leading
+/// coefficient into irreducibles, by factoring it modulo a prime,
+/// Hensel lifting the factors and recombining them.
+fn zassenhaus(f: &[BigInt]) -> Vec<Vec<BigInt>> {
+    if f.len() <= 2 {
+        return vec![f.to_vec()];
+    }
+
+    let lc = f.last().unwrap().clone();
+    let (field, modular) = (3u64..)
+        .filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0))
+        .map(Fp)
+        .find_map(|field| {
+            if (&lc % field.0).is_zero() {
+                return None;
+            }
+            let g = field.monic(&field.reduce(f));
+            let square_free = field.gcd(&g, &field.derivative(&g)).len() == 1;
+            square_free.then_some((field, g))
+        })
+        .unwrap();
+
+    let factors = field.factor(&modular);
+    if factors.len() == 1 {
+        return vec![f.to_vec()];
+    }
+
+    // lift until p^k is more than twice the Mignotte bound on the
+    // coefficients of any factor, times the leading coefficient
+    let norm = f.iter().map(|c| c * c).fold(BigInt::zero(), |s, c| s + c).sqrt() + 1;
+    let bound = norm * (BigInt::one() << (f.len() - 1)) * &lc;
+    let mut modulus = BigInt::from(field.0);
+    while modulus <= &bound * 2 {
+        modulus = &modulus * &modulus;
+    }
+
+    let inv = lc.modinv(&modulus).unwrap();
+    let monic: Vec<BigInt> = f.iter().map(|c| (c * &inv).mod_floor(&modulus)).collect();
+    let mut lifted = hensel_lift(&monic, &factors, field, &modulus);
+
+    // try products of the lifted factors, smallest first
+    let mut f = f.to_vec();
+    let mut res = vec![];
+    let mut size = 1;
+    'sizes: while 2 * size <= lifted.len() {
+        for subset in combinations(lifted.len(), size) {
+            let lc = f.last().unwrap().clone();
+            let product = subset.iter().fold(vec![lc], |p, &i| zm_mul(&p, &lifted[i], &modulus));
+            let candidate = z_primitive(&symmetric(&product, &modulus));
+            let Some(quot) = z_div_exact(&f, &candidate) else {
+                continue;
+            };
+
+            res.push(candidate);
+            f = quot;
+            for &i in subset.iter().rev() {
+                lifted.remove(i);
+            }
+            continue 'sizes;
+        }
+        size += 1;
+    }
+
+    res.push(z_primitive(&f));
+    res
+}
+
+/// Lifts `f = prod(factors) mod p`, where all are monic, to the same
+/// factorization modulo `modulus`, a power of `p`.
+fn hensel_lift(f: &[BigInt], factors: &[Vec<u64>], field: Fp, modulus: &BigInt) -> Vec<Vec<BigInt>> {
+    if factors.len() == 1 {
+        return vec![f.to_vec()];
+    }
+
+    let (left, right) = factors.split_at(factors.len() / 2);
+    let g0 = left.iter().fold(vec![1], |p, g| field.mul(&p, g));
+    let h0 = right.iter().fold(vec![1], |p, h| field.mul(&p, h));
+    let (_, s0, t0) = field.ext_gcd(&g0, &h0);
+
+    let lift = |f: &[u64]| -> Vec<BigInt> { f.iter().map(|&c| BigInt::from(c)).collect() };
+    let (mut g, mut h, mut s, mut t) = (lift(&g0), lift(&h0), lift(&s0), lift(&t0));
+    let mut m = BigInt::from(field.0);
+    while &m < modulus {
+        m = &m * &m;
+        let f = zm_reduce(f, &m);
+
+        // one quadratic Hensel step, from von zur Gathen and Gerhard's
+        // Modern Computer Algebra, algorithm 15.10
+        let e = zm_sub(&f, &zm_mul(&g, &h, &m), &m);
+        let (q, r) = zm_divrem(&zm_mul(&s, &e, &m), &h, &m);
+        let g1 = zm_add(&zm_add(&g, &zm_mul(&t, &e, &m), &m), &zm_mul(&q, &g, &m), &m);
+        let h1 = zm_add(&h, &r, &m);
+
+        let b = zm_sub(&zm_add(&zm_mul(&s, &g1, &m), &zm_mul(&t, &h1, &m), &m), &[BigInt::one()], &m);
+        let (c, d) = zm_divrem(&zm_mul(&s, &b, &m), &h1, &m);
+        s = zm_sub(&s, &d, &m);
+        t = zm_sub(&zm_sub(&t, &zm_mul(&t, &b, &m), &m), &zm_mul(&c, &g1, &m), &m);
+        g = g1;
+        h = h1;
+    }
+
+    let (g, h) = (zm_reduce(&g, modulus), zm_reduce(&h, modulus));
+    let mut res = hensel_lift(&g, left, field, modulus);
+    res.extend(hensel_lift(&h, right, field, modulus));
+    res
+}
+
+fn zm_reduce(f: &[BigInt], m: &BigInt) -> Vec<BigInt> {
+    z_trim(f.iter().map(|c| c.mod_floor(m)).collect())
+}
+
+fn zm_add(a: &[BigInt], b: &[BigInt], m: &BigInt) -> Vec<BigInt> {
+    zm_reduce(&z_sub(a, &b.iter().map(|c| -c).collect::<Vec<_>>()), m)
+}
+
+fn zm_sub(a: &[BigInt], b: &[BigInt], m: &BigInt) -> Vec<BigInt> {
+    zm_reduce(&z_sub(a, b), m)
+}
+
+fn zm_mul(a: &[BigInt], b: &[BigInt], m: &BigInt) -> Vec<BigInt> {
+    zm_reduce(&z_mul(a, b), m)
+}
+
+/// Divides `a` by the monic `b` modulo `m`.
+fn zm_divrem(a: &[BigInt], b: &[BigInt], m: &BigInt) -> (Vec<BigInt>, Vec<BigInt>) {
+    if a.len() < b.len() {
+        return (vec![], a.to_vec());
+    }
+
+    let mut rem = a.to_vec();
+    let mut quot = vec![BigInt::zero(); a.len() - b.len() + 1];
+    for i in (0..quot.len()).rev() {
+        let q = rem[i + b.len() - 1].mod_floor(m);
+        for (j, c) in b.iter().enumerate() {
+            rem[i + j] = (&rem[i + j] - &q * c).mod_floor(m);
+        }
+        quot[i] = q;
+    }
+    (z_trim(quot), zm_reduce(&rem, m))
+}
+
+/// The coefficients of `f` in `(-m/2, m/2]`.
+fn symmetric(f: &[BigInt], m: &BigInt) -> Vec<BigInt> {
+    let half = m / 2;
+    z_trim(f.iter().map(|c| if c > &half { c - m } else { c.clone() }).collect())
+}
+
+/// The integers modulo a small odd prime, which fits in 32 bits so that
+/// products don't overflow.
+#[derive(Debug, Clone, Copy)]
+struct Fp(u64);
+
+impl Fp {
+    fn reduce(&self, f: &[BigInt]) -> Vec<u64> {
+        let p = BigInt::from(self.0);
+        self.trim(f.iter().map(|c| c.mod_floor(&p).to_u64().unwrap()).collect())
+    }
+
+    fn trim(&self, mut f: Vec<u64>) -> Vec<u64> {
+        while f.last() == Some(&0) {
+            f.pop();
+        }
+        f
+    }
+
+    fn inv(&self, a: u64) -> u64 {
+        let (mut result, mut base, mut exp) = (1, a % self.0, self.0 - 2);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base % self.0;
+            }
+            base = base * base % self.0;
+            exp >>= 1;
+        }
+        result
+    }
+
+    fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut res = vec![0; a.len().max(b.len())];
+        for (i, c) in res.iter_mut().enumerate() {
+            let (x, y) = (a.get(i).copied().unwrap_or(0), b.get(i).copied().unwrap_or(0));
+            *c = (x + self.0 - y) % self.0;
+        }
+        self.trim(res)
+    }
+
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        if a.is_empty() || b.is_empty() {
+            return vec![];
+        }
+        let mut res = vec![0; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                res[i + j] = (res[i + j] + x * y) % self.0;
+            }
+        }
+        self.trim(res)
+    }
+
+    fn scale(&self, f: &[u64], k: u64) -> Vec<u64> {
+        self.trim(f.iter().map(|c| c * k % self.0).collect())
+    }
+
+    fn monic(&self, f: &[u64]) -> Vec<u64> {
+        match f.last() {
+            Some(&lc) => self.scale(f, self.inv(lc)),
+            None => vec![],
+        }
+    }
+
+    fn derivative(&self, f: &[u64]) -> Vec<u64> {
+        self.trim(f.iter().enumerate().skip(1).map(|(i, c)| c * (i as u64 % self.0) % self.0).collect())
+    }
+
+    fn divrem(&self, a: &[u64], b: &[u64]) -> (Vec<u64>, Vec<u64>) {
+        if a.len() < b.len() {
+            return (vec![], a.to_vec());
+        }
+
+        let inv = self.inv(*b.last().unwrap());
+        let mut rem = a.to_vec();
+        let mut quot = vec![0; a.len() - b.len() + 1];
+        for i in (0..quot.len()).rev() {
+            let q = rem[i + b.len() - 1] * inv % self.0;
+            for (j, c) in b.iter().enumerate() {
+                rem[i + j] = (rem[i + j] + self.0 - q * c % self.0) % self.0;
+            }
+            quot[i] = q;
+        }
+        (self.trim(quot), self.trim(rem))
+    }
+
+    /// The monic greatest common divisor.
+    fn gcd(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let (mut a, mut b) = (a.to_vec(), b.to_vec());
+        while !b.is_empty() {
+            let (_, r) = self.divrem(&a, &b);
+            a = b;
+            b = r;
+        }
+        self.monic(&a)
+    }
+
+    /// `(g, s, t)` with `s * a + t * b = g`, the monic gcd of `a` and `b`.
+    fn ext_gcd(&self, a: &[u64], b: &[u64]) -> (Vec<u64>, Vec<u64>, Vec<u64>) {
+        let (mut r0, mut r1) = (a.to_vec(), b.to_vec());
+        let (mut s0, mut s1) = (vec![1], vec![]);
+        let (mut t0, mut t1) = (vec![], vec![1]);
+        while !r1.is_empty() {
+            let (q, r) = self.divrem(&r0, &r1);
+            let s = self.sub(&s0, &self.mul(&q, &s1));
+            let t = self.sub(&t0, &self.mul(&q, &t1));
+            r0 = std::mem::replace(&mut r1, r);
+            s0 = std::mem::replace(&mut s1, s);
+            t0 = std::mem::replace(&mut t1, t);
+        }
+
+        let inv = self.inv(*r0.last().unwrap());
+        (self.scale(&r0, inv), self.scale(&s0, inv), self.scale(&t0, inv))
+    }
+
+    /// `base^exp mod m`.
+    fn pow_mod(&self, base: &[u64], exp: &BigUint, m: &[u64]) -> Vec<u64> {
+        let mut result = vec![1];
+        let base = self.divrem(base, m).1;
+        for i in (0..exp.bits()).rev() {
+            result = self.divrem(&self.mul(&result, &result), m).1;
+            if exp.bit(i) {
+                result = self.divrem(&self.mul(&result, &base), m).1;
+            }
+        }
+        result
+    }
+
+    /// Factors a monic, square-free `f` into monic irreducibles.
+    fn factor(&self, f: &[u64]) -> Vec<Vec<u64>> {
+        let mut rng = 0x2545_f491_4f6c_dd1d_u64;
+        self.distinct_degree(f)
+            .into_iter()
+            .flat_map(|(g, d)| self.equal_degree(&g, d, &mut rng))
+            .collect()
+    }
+
+    /// Splits `f` into the products of its irreducible factors of each degree.
+    fn distinct_degree(&self, f: &[u64]) -> Vec<(Vec<u64>, usize)> {
+        let x = vec![0, 1];
+        let p = BigUint::from(self.0);
+        let mut res = vec![];
+        let mut f = f.to_vec();
+        let mut h = x.clone();
+        let mut d = 1;
+        while f.len() > 2 * d {
+            // h = x^(p^d) mod f
+            h = self.pow_mod(&h, &p, &f);
+            let g = self.gcd(&f, &self.sub(&h, &x));
+            if g.len() > 1 {
+                f = self.divrem(&f, &g).0;
+                h = self.divrem(&h, &f).1;
+                res.push((g, d));
+            }
+            d += 1;
+        }
+        if f.len() > 1 {
+            let d = f.len() - 1;
+            res.push((f, d));
+        }
+        res
+    }
+
+    /// Cantor-Zassenhaus splitting of a product of irreducibles of degree `d`.
+    fn equal_degree(&self, f: &[u64], d: usize, rng: &mut u64) -> Vec<Vec<u64>> {
+        if f.len() - 1 <= d {
+            return vec![f.to_vec()];
+        }
+
+        let exp = (BigUint::from(self.0).pow(d as u32) - 1u32) >> 1;
+        loop {
+            let a: Vec<u64> = (0..f.len() - 1)
+                .map(|_| {
+                    *rng ^= *rng << 13;
+                    *rng ^= *rng >> 7;
+                    *rng ^= *rng << 17;
+                    *rng % self.0
+                })
+                .collect();
+            let a = self.trim(a);
+            if a.len() < 2 {
+                continue;
+            }
+
+            let b = self.sub(&self.pow_mod(&a, &exp, f), &[1]);
+            let g = self.gcd(&b, f);
+            if g.len() > 1 && g.len() < f.len() {
+                let h = self.divrem(f, &g).0;
+                let mut res = self.equal_degree(&g, d, rng);
+                res.extend(self.equal_degree(&h, d, rng));
+                return res;
+            }
+        }
+    }
+}
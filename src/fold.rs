@@ -0,0 +1,73 @@
+use std::ops::ControlFlow;
+
+use crate::expr::{Expr, ExprRef};
+
+/// Folds an expression tree into a single value, from the leaves up.
+pub trait Fold {
+    type Output;
+
+    /// Called on each node before its args are folded. Returning a value
+    /// uses it for the node and skips folding its args.
+    fn fold_pre(&mut self, _expr: ExprRef) -> ControlFlow<(), Option<Self::Output>> {
+        ControlFlow::Continue(None)
+    }
+
+    /// Folds a node, given its args already folded, in the same order
+    /// as `Basic::args`.
+    fn fold(&mut self, expr: ExprRef, args: Vec<Self::Output>) -> ControlFlow<(), Self::Output>;
+}
+
+pub fn fold<F: Fold + ?Sized>(expr: ExprRef, f: &mut F) -> ControlFlow<(), F::Output> {
+    if let Some(output) = f.fold_pre(expr)? {
+        return ControlFlow::Continue(output);
+    }
+
+    let mut args = vec![];
+    for arg in expr.args() {
+        args.push(fold(arg.rf(), f)?);
+    }
+
+    f.fold(expr, args)
+}
+
+/// Rewrites an expression tree one node at a time.
+///
+/// Implementations only need to handle the nodes they care about and
+/// return every other node unchanged. Whether the args of a node have
+/// already been transformed depends on the driver, [`bottom_up`] or
+/// [`top_down`].
+pub trait Transformer {
+    fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr>;
+}
+
+/// Rebuilds `expr` from new args, or returns it unchanged for nodes
+/// without any.
+pub fn rebuild(expr: ExprRef, args: Vec<Expr>) -> Expr {
+    if args.is_empty() {
+        expr.into_expr()
+    } else {
+        expr.with_args(args).unwrap_or_else(|| expr.into_expr())
+    }
+}
+
+/// Transforms the args of each node, then the node rebuilt from them.
+pub fn bottom_up<T: Transformer + ?Sized>(expr: ExprRef, t: &mut T) -> ControlFlow<(), Expr> {
+    let mut args = vec![];
+    for arg in expr.args() {
+        args.push(bottom_up(arg.rf(), t)?);
+    }
+
+    t.transform(rebuild(expr, args))
+}
+
+/// Transforms each node, then the args of the node it was transformed into.
+pub fn top_down<T: Transformer + ?Sized>(expr: ExprRef, t: &mut T) -> ControlFlow<(), Expr> {
+    let expr = t.transform(expr.into_expr())?;
+
+    let mut args = vec![];
+    for arg in expr.args() {
+        args.push(top_down(arg.rf(), t)?);
+    }
+
+    ControlFlow::Continue(rebuild(expr.rf(), args))
+}
@@ -1,10 +1,82 @@
 
+/// Declares a function node that takes a single argument, along
+/// with its constructor and `Basic` impl.
+macro_rules! unary_function {
+    ($(#[$meta:meta])* $name:ident, $ctor:ident, $visit:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Eq, PartialOrd, Ord)]
+        pub struct $name {
+            arg: $crate::expr::Expr,
+        }
+
+        impl $name {
+            pub fn new(arg: $crate::expr::Expr) -> Self {
+                Self {
+                    arg,
+                }
+            }
+
+            pub fn arg(&self) -> $crate::expr::ExprRef<'_> {
+                self.arg.rf()
+            }
+        }
+
+        pub fn $ctor(arg: impl Into<$crate::expr::Expr>) -> $crate::expr::Expr<$name> {
+            $crate::expr::Expr::new($name::new(arg.into()))
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool {
+                PartialEq::eq(&self.arg, &other.arg)
+            }
+        }
+
+        impl $crate::basic::Basic for $name {
+            fn visit(self: $crate::expr::ExprRef<Self>, visitor: &mut dyn $crate::visitor::Visitor) -> ::std::ops::ControlFlow<()> {
+                visitor.$visit(self)
+            }
+
+            fn eq(&self, other: &dyn $crate::basic::Basic) -> bool {
+                if let Some(other) = other.downcast::<Self>() {
+                    self == other
+                } else {
+                    false
+                }
+            }
+
+            fn cmp(&self, other: &dyn $crate::basic::Basic) -> Option<::std::cmp::Ordering> {
+                other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+            }
+
+            fn args(&self) -> Vec<$crate::expr::Expr> {
+                vec![self.arg.clone()]
+            }
+
+            fn with_args(&self, args: Vec<$crate::expr::Expr>) -> Option<$crate::expr::Expr> {
+                let arg = args.into_iter().next()?;
+                Some($crate::expr::Expr::new($name::new(arg)))
+            }
+        }
+    };
+}
+
 mod times;
 mod plus;
 mod derivative;
+mod pow;
+mod exp;
+mod log;
+mod trig;
+mod hyperbolic;
+mod applied;
 
 // pub use self::add::Add;
 pub use self::times::{Times, times};
 pub use self::plus::{Plus, plus};
 pub use self::derivative::{Derivative, derivative};
-
+pub use self::pow::{Pow, pow};
+pub use self::exp::{Exp, exp};
+pub use self::log::{Log, log, log_base};
+pub use self::trig::{Sin, sin, Cos, cos, Tan, tan, Cot, cot, Sec, sec, Csc, csc, Asin, asin, Acos, acos, Atan, atan, Atan2, atan2};
+pub use self::hyperbolic::{Sinh, sinh, Cosh, cosh, Tanh, tanh, Asinh, asinh, Acosh, acosh, Atanh, atanh};
+pub use self::applied::{AppliedFunction, apply};
@@ -0,0 +1,110 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use string_cache::DefaultAtom;
+
+use crate::{basic::Basic, expr::{Expr, ExprRef}, visitor::Visitor};
+
+/// This corresponds to an undefined function applied to some
+/// arguments, `f(arg, ...)`.
+///
+/// `partials` records how many times each argument slot has been
+/// differentiated, so `f^(1, 0)(x^2, y)` is the partial derivative of
+/// `f` in its first slot, evaluated at `(x^2, y)`.
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
+pub struct AppliedFunction {
+    name: DefaultAtom,
+    args: Vec<Expr>,
+    partials: Vec<u32>,
+}
+
+impl AppliedFunction {
+    pub fn new(name: &str, args: Vec<Expr>) -> Self {
+        let partials = vec![0; args.len()];
+        Self {
+            name: DefaultAtom::from(name),
+            args,
+            partials,
+        }
+    }
+
+    /// Like `new`, with `partials[i]` derivatives taken in slot `i`.
+    /// Returns `None` when there isn't one count per argument.
+    pub fn with_partials(name: &str, args: Vec<Expr>, partials: Vec<u32>) -> Option<Self> {
+        (args.len() == partials.len()).then(|| Self {
+            name: DefaultAtom::from(name),
+            args,
+            partials,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn args(&self) -> &[Expr] {
+        &self.args
+    }
+
+    pub fn partials(&self) -> &[u32] {
+        &self.partials
+    }
+
+    /// Rebuilds this application with new arguments, keeping the name
+    /// and partial derivatives. Returns `None` when the number of
+    /// arguments changes.
+    pub fn reapply(&self, args: Vec<Expr>) -> Option<Self> {
+        Self::with_partials(self.name(), args, self.partials.clone())
+    }
+
+    /// The partial derivative of the function in slot `i`, evaluated
+    /// at the same arguments.
+    pub fn partial(&self, i: usize) -> Self {
+        let mut partials = self.partials.clone();
+        partials[i] += 1;
+        Self {
+            name: self.name.clone(),
+            args: self.args.clone(),
+            partials,
+        }
+    }
+}
+
+/// Applies the undefined function `name` to `args`.
+pub fn apply<A: Into<Expr>>(name: &str, args: impl IntoIterator<Item = A>) -> Expr<AppliedFunction> {
+    Expr::new(AppliedFunction::new(name, args.into_iter().map(Into::into).collect()))
+}
+
+impl PartialEq for AppliedFunction {
+    fn eq(&self, other: &AppliedFunction) -> bool {
+        self.name == other.name
+            && self.partials == other.partials
+            && self.args.len() == other.args.len()
+            && self.args.iter().zip(&other.args).all(|(a, b)| PartialEq::eq(a, b))
+    }
+}
+
+impl Basic for AppliedFunction {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_applied_function(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        if let Some(other) = other.downcast::<Self>() {
+            self == other
+        } else {
+            false
+        }
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+
+    fn args(&self) -> Vec<Expr> {
+        self.args.clone()
+    }
+
+    fn with_args(&self, args: Vec<Expr>) -> Option<Expr> {
+        self.reapply(args).map(|f| Expr::new(f) as Expr)
+    }
+}
@@ -1,8 +1,8 @@
-use std::{mem, ops::ControlFlow};
+use std::ops::ControlFlow;
 
-use crate::{Number, basic::Basic, expr::{Expr, ExprRef}, number::{ZERO, ONE}, symbol::Symbol, undefined::{UNDEFINED, Undefined}, visitor::Visitor};
+use crate::{Number, basic::Basic, canonicalize::canonicalize, constant::Constant, expr::{Expr, ExprRef}, fold::{Fold, fold}, number::{ZERO, ONE, MINUS_ONE}, symbol::{Symbol, depends_on}, undefined::Undefined, visitor::Visitor};
 
-use super::{Plus, Times};
+use super::{Acos, Acosh, AppliedFunction, Asin, Asinh, Atan, Atan2, Atanh, Cos, Cosh, Cot, Csc, Exp, Log, Plus, Pow, Sec, Sin, Sinh, Tan, Tanh, Times, cos, cosh, cot, log, sin, sinh, tan};
 
 pub fn derivative(expr: Expr, wrt: Expr<Symbol>) -> Expr<Derivative> {
     Expr::new(Derivative::new(expr, wrt))
@@ -21,22 +21,14 @@ impl Derivative {
 
     /// Temporary
     pub fn eval(&self) -> ControlFlow<(), Expr> {
-        let mut visitor = DerivVisitor {
-            wrt: &*self.wrt,
-            res: UNDEFINED.clone(),
-        };
-
-        match self.expr.rf().visit(&mut visitor) {
-            ControlFlow::Continue(_) => ControlFlow::Continue(visitor.res),
-            ControlFlow::Break(_) => ControlFlow::Break(()),
-        }
+        fold(self.expr.rf(), &mut Differentiate { wrt: &self.wrt })
     }
     
-    pub fn wrt(&self) -> ExprRef<Symbol> {
+    pub fn wrt(&self) -> ExprRef<'_, Symbol> {
         self.wrt.rf()
     }
 
-    pub fn expr(&self) -> ExprRef {
+    pub fn expr(&self) -> ExprRef<'_> {
         self.expr.rf()
     }
 }
@@ -55,108 +47,189 @@ impl Basic for Derivative {
     }
 
     fn cmp(&self, other: &dyn Basic) -> Option<std::cmp::Ordering> {
-        if let Some(other) = other.downcast::<Self>() {
-            Some(Ord::cmp(self, other))
-        } else {
-            None
-        }
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+
+    fn args(&self) -> Vec<Expr> {
+        vec![self.expr.clone()]
+    }
+
+    fn with_args(&self, args: Vec<Expr>) -> Option<Expr> {
+        let expr = args.into_iter().next()?;
+        Some(Expr::new(Derivative::new(expr, self.wrt.clone())))
     }
 }
 
-impl ::core::cmp::PartialEq for Derivative {
-    #[inline]
+impl PartialEq for Derivative {
     fn eq(&self, other: &Derivative) -> bool {
-        match *other {
-            Derivative {
-                wrt: ref __self_1_0,
-                expr: ref __self_1_1,
-            } => match *self {
-                Derivative {
-                    wrt: ref __self_0_0,
-                    expr: ref __self_0_1,
-                } => (*__self_0_0) == (*__self_1_0) && (__self_0_1) == (__self_1_1),
-            },
-        }
-    }
-    #[inline]
-    fn ne(&self, other: &Derivative) -> bool {
-        match *other {
-            Derivative {
-                wrt: ref __self_1_0,
-                expr: ref __self_1_1,
-            } => match *self {
-                Derivative {
-                    wrt: ref __self_0_0,
-                    expr: ref __self_0_1,
-                } => (*__self_0_0) != (*__self_1_0) || (__self_0_1) != (__self_1_1),
-            },
-        }
+        self.wrt == other.wrt && PartialEq::eq(&self.expr, &other.expr)
     }
 }
 
-pub struct DerivVisitor<'a> {
+/// Differentiates each node given the derivatives of its args.
+struct Differentiate<'a> {
     wrt: &'a Symbol,
-    res: Expr,
 }
 
-impl Visitor for DerivVisitor<'_> {
-    fn visit_undefined(&mut self, _undef: ExprRef<Undefined>) -> ControlFlow<()> {
-        self.res = UNDEFINED.clone();
-        ControlFlow::Break(())
-    }
+impl Differentiate<'_> {
+    /// The derivative of an applied function or custom node by the chain rule,
+    /// `d/dx f(u_1, ..., u_n) = sum_i D_i f(u_1, ..., u_n) * du_i/dx`, where
+    /// `partial(i)` gives `D_i f`.
+    fn chain_rule(
+        &self,
+        args: &[Expr],
+        arg_primes: Vec<Expr>,
+        mut partial: impl FnMut(usize) -> Option<Expr>,
+    ) -> Option<Expr> {
+        let mut terms = vec![];
+        for (i, (arg, arg_prime)) in args.iter().zip(arg_primes).enumerate() {
+            if depends_on(arg.rf(), self.wrt) {
+                terms.push(partial(i)? * arg_prime);
+            }
+        }
 
-    fn visit_number(&mut self, _n: ExprRef<Number>) -> ControlFlow<()> {
-        self.res = ZERO.clone();
-        ControlFlow::Continue(())
+        Some(Expr::new(Plus::new(terms)))
     }
 
-    fn visit_symbol(&mut self, sym: ExprRef<Symbol>) -> ControlFlow<()> {
-        if &*sym == self.wrt {
-            self.res = ONE.clone();
-        } else {
-            self.res = ZERO.clone();
-        }
-        ControlFlow::Continue(())
+    fn applied_function(&self, f: ExprRef<AppliedFunction>, arg_primes: Vec<Expr>) -> Expr {
+        let partial = |i: usize| -> Option<Expr> {
+            let only_in_slot = |sym: &Symbol| {
+                f.args().iter().enumerate().all(|(j, other)| i == j || !depends_on(other.rf(), sym))
+            };
+
+            // A symbol that only appears in this slot can name the partial
+            // derivative, which keeps the usual `d/dx f(x)` notation.
+            Some(match f.args()[i].downcast_expr::<Symbol>() {
+                Ok(sym) if only_in_slot(&sym) => derivative(f.into_expr(), sym).into(),
+                _ => Expr::new(f.partial(i)),
+            })
+        };
+
+        self.chain_rule(f.args(), arg_primes, partial).unwrap()
     }
+}
 
-    fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()> {
-        let mut terms = vec![];
-        for term in plus.terms() {
-            term.rf().visit(self)?;
-            terms.push(self.res.clone());
+impl Fold for Differentiate<'_> {
+    type Output = Expr;
+
+    fn fold_pre(&mut self, expr: ExprRef) -> ControlFlow<(), Option<Expr>> {
+        if expr.is::<Undefined>() {
+            return ControlFlow::Break(());
         }
 
-        self.res = Expr::new(Plus::new(terms));
-        ControlFlow::Continue(())
-    }
+        let Some(derivative) = expr.downcast::<Derivative>() else {
+            return ControlFlow::Continue(None);
+        };
 
-    fn visit_times(&mut self, times: ExprRef<Times>) -> ControlFlow<()> {
-        let mut plus_terms: Vec<Expr> = vec![];
-        for i in 0..times.terms().len() {
-            let mut times_terms = vec![];
-            for (j, term) in times.terms().iter().enumerate() {
-                if i == j {
-                    term.rf().visit(self)?;
-                    times_terms.push(self.res.clone());
-                } else {
-                    times_terms.push(term.clone());
-                }
+        let inner = canonicalize(derivative.eval()?);
+        ControlFlow::Continue(Some(match inner.downcast::<Derivative>() {
+            // The inner derivative could not be taken any further. Partial
+            // derivatives with respect to independent symbols commute, so
+            // differentiate inside it instead.
+            Some(inner) => {
+                let prime = fold(inner.expr(), self)?;
+                super::derivative(prime, inner.wrt().into_expr()).into()
             }
-            plus_terms.push(Expr::new(Times::new(times_terms)));
-        }
-
-        self.res = Expr::new(Plus::new(plus_terms));
-        ControlFlow::Continue(())
+            None => fold(inner.rf(), self)?,
+        }))
     }
 
-    fn visit_derivative(&mut self, derivative: ExprRef<Derivative>) -> ControlFlow<()> {
-        let mut visitor = DerivVisitor {
-            wrt: &*self.wrt,
-            res: UNDEFINED.clone(),
-        };
+    fn fold(&mut self, expr: ExprRef, args: Vec<Expr>) -> ControlFlow<(), Expr> {
+        // `args` holds the derivatives of the args of `expr`
+        let prime = |i: usize| args[i].clone();
+
+        ControlFlow::Continue(if expr.is::<Number>() || expr.is::<Constant>() {
+            ZERO.clone()
+        } else if let Some(sym) = expr.downcast::<Symbol>() {
+            if sym == self.wrt {
+                ONE.clone()
+            } else {
+                ZERO.clone()
+            }
+        } else if expr.is::<Plus>() {
+            Expr::new(Plus::new(args))
+        } else if let Some(times) = expr.downcast::<Times>() {
+            let mut plus_terms: Vec<Expr> = vec![];
+            for i in 0..times.terms().len() {
+                let mut times_terms = times.terms().to_vec();
+                times_terms[i] = prime(i);
+                plus_terms.push(Expr::new(Times::new(times_terms)));
+            }
 
-        derivative.expr.rf().visit(&mut visitor)?;
-        self.res = visitor.res;
-        ControlFlow::Continue(())
+            Expr::new(Plus::new(plus_terms))
+        } else if let Some(pow) = expr.downcast::<Pow>() {
+            let base = pow.base().into_expr();
+            let exp = pow.exp().into_expr();
+
+            if !depends_on(pow.exp(), self.wrt) {
+                // d/dx u^n = n * u^(n - 1) * du/dx
+                Expr::new(Times::new([
+                    exp.clone(),
+                    base.pow(exp + MINUS_ONE.clone()),
+                    prime(0),
+                ]))
+            } else {
+                // d/dx u^v = u^v * (dv/dx * ln(u) + v * du/dx / u)
+                expr.into_expr() * (prime(1) * log(base.clone()) + exp * prime(0) / base)
+            }
+        } else if expr.is::<Exp>() {
+            // d/dx e^u = e^u * du/dx
+            expr.into_expr() * prime(0)
+        } else if let Some(log) = expr.downcast::<Log>() {
+            match log.base() {
+                // log_b(u) = ln(u) / ln(b)
+                Some(base) => return fold((super::log(log.arg()) / super::log(base)).rf(), self),
+                // d/dx ln(u) = du/dx / u
+                None => prime(0) / log.arg(),
+            }
+        } else if let Some(sin) = expr.downcast::<Sin>() {
+            cos(sin.arg()) * prime(0)
+        } else if let Some(cos) = expr.downcast::<Cos>() {
+            -sin(cos.arg()) * prime(0)
+        } else if expr.is::<Tan>() {
+            // tan' = 1 + tan^2
+            (expr.pow(2) + 1) * prime(0)
+        } else if expr.is::<Cot>() {
+            // cot' = -(1 + cot^2)
+            -(expr.pow(2) + 1) * prime(0)
+        } else if let Some(sec) = expr.downcast::<Sec>() {
+            expr * tan(sec.arg()) * prime(0)
+        } else if let Some(csc) = expr.downcast::<Csc>() {
+            -(expr * cot(csc.arg())) * prime(0)
+        } else if let Some(asin) = expr.downcast::<Asin>() {
+            // asin' = (1 - u^2)^(-1/2)
+            (-asin.arg().pow(2) + 1).pow(Expr::new(Number::rational(-1, 2))) * prime(0)
+        } else if let Some(acos) = expr.downcast::<Acos>() {
+            // acos' = -(1 - u^2)^(-1/2)
+            -(-acos.arg().pow(2) + 1).pow(Expr::new(Number::rational(-1, 2))) * prime(0)
+        } else if let Some(atan) = expr.downcast::<Atan>() {
+            // atan' = 1 / (1 + u^2)
+            (atan.arg().pow(2) + 1).pow(-1) * prime(0)
+        } else if let Some(atan2) = expr.downcast::<Atan2>() {
+            // d/dx atan2(y, x) = (x * dy/dx - y * dx/dx) / (x^2 + y^2)
+            let (y, x) = (atan2.y(), atan2.x());
+            (x * prime(0) - y * prime(1)) / (x.pow(2) + y.pow(2))
+        } else if let Some(sinh) = expr.downcast::<Sinh>() {
+            cosh(sinh.arg()) * prime(0)
+        } else if let Some(cosh) = expr.downcast::<Cosh>() {
+            sinh(cosh.arg()) * prime(0)
+        } else if expr.is::<Tanh>() {
+            // tanh' = 1 - tanh^2
+            (-expr.pow(2) + 1) * prime(0)
+        } else if let Some(asinh) = expr.downcast::<Asinh>() {
+            // asinh' = (u^2 + 1)^(-1/2)
+            (asinh.arg().pow(2) + 1).pow(Expr::new(Number::rational(-1, 2))) * prime(0)
+        } else if let Some(acosh) = expr.downcast::<Acosh>() {
+            // acosh' = (u^2 - 1)^(-1/2)
+            (acosh.arg().pow(2) - 1).pow(Expr::new(Number::rational(-1, 2))) * prime(0)
+        } else if let Some(atanh) = expr.downcast::<Atanh>() {
+            // atanh' = 1 / (1 - u^2)
+            (-atanh.arg().pow(2) + 1).pow(-1) * prime(0)
+        } else if let Ok(f) = expr.downcast_exprref::<AppliedFunction>() {
+            self.applied_function(f, args)
+        } else {
+            self.chain_rule(&expr.args(), args, |i| expr.partial_derivative(i))
+                .unwrap_or_else(|| derivative(expr.into_expr(), Expr::new(self.wrt.clone())).into())
+        })
     }
 }
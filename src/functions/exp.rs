@@ -0,0 +1,58 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use crate::{basic::Basic, expr::{Expr, ExprRef}, visitor::Visitor};
+
+/// This corresponds to `e^arg`
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
+pub struct Exp {
+    arg: Expr,
+}
+
+impl Exp {
+    pub fn new(arg: Expr) -> Self {
+        Self {
+            arg,
+        }
+    }
+
+    pub fn arg(&self) -> ExprRef<'_> {
+        self.arg.rf()
+    }
+}
+
+pub fn exp(arg: impl Into<Expr>) -> Expr<Exp> {
+    Expr::new(Exp::new(arg.into()))
+}
+
+impl PartialEq for Exp {
+    fn eq(&self, other: &Exp) -> bool {
+        PartialEq::eq(&self.arg, &other.arg)
+    }
+}
+
+impl Basic for Exp {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_exp(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        if let Some(other) = other.downcast::<Self>() {
+            self == other
+        } else {
+            false
+        }
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+
+    fn args(&self) -> Vec<Expr> {
+        vec![self.arg.clone()]
+    }
+
+    fn with_args(&self, args: Vec<Expr>) -> Option<Expr> {
+        let arg = args.into_iter().next()?;
+        Some(Expr::new(Exp::new(arg)))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{Number, expr::Expr, number::{ONE, ZERO}};
+
+unary_function!(
+    /// This corresponds to `sinh(arg)`
+    Sinh, sinh, visit_sinh
+);
+unary_function!(
+    /// This corresponds to `cosh(arg)`
+    Cosh, cosh, visit_cosh
+);
+unary_function!(
+    /// This corresponds to `tanh(arg)`
+    Tanh, tanh, visit_tanh
+);
+unary_function!(
+    /// This corresponds to `asinh(arg)`
+    Asinh, asinh, visit_asinh
+);
+unary_function!(
+    /// This corresponds to `acosh(arg)`
+    Acosh, acosh, visit_acosh
+);
+unary_function!(
+    /// This corresponds to `atanh(arg)`
+    Atanh, atanh, visit_atanh
+);
+
+// Exact values. These expect the arguments to already be canonical.
+
+fn is_zero(expr: &Expr) -> bool {
+    expr.downcast::<Number>().is_some_and(Number::is_zero)
+}
+
+impl Sinh {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if let Some(asinh) = self.arg.downcast::<Asinh>() {
+            Some(asinh.arg().into_expr())
+        } else if is_zero(&self.arg) {
+            Some(ZERO.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl Cosh {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if let Some(acosh) = self.arg.downcast::<Acosh>() {
+            Some(acosh.arg().into_expr())
+        } else if is_zero(&self.arg) {
+            Some(ONE.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl Tanh {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if let Some(atanh) = self.arg.downcast::<Atanh>() {
+            Some(atanh.arg().into_expr())
+        } else if is_zero(&self.arg) {
+            Some(ZERO.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl Asinh {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if is_zero(&self.arg) {
+            Some(ZERO.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl Acosh {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if self.arg.downcast::<Number>().is_some_and(Number::is_one) {
+            Some(ZERO.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl Atanh {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if is_zero(&self.arg) {
+            Some(ZERO.clone())
+        } else {
+            None
+        }
+    }
+}
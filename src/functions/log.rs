@@ -0,0 +1,77 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use crate::{basic::Basic, expr::{Expr, ExprRef}, visitor::Visitor};
+
+/// This corresponds to `log_base(arg)`, or `ln(arg)` when there is no base.
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
+pub struct Log {
+    arg: Expr,
+    base: Option<Expr>,
+}
+
+impl Log {
+    pub fn new(arg: Expr, base: Option<Expr>) -> Self {
+        Self {
+            arg,
+            base,
+        }
+    }
+
+    pub fn arg(&self) -> ExprRef<'_> {
+        self.arg.rf()
+    }
+
+    /// Returns `None` for the natural logarithm.
+    pub fn base(&self) -> Option<ExprRef<'_>> {
+        self.base.as_ref().map(Expr::rf)
+    }
+
+    pub fn is_natural(&self) -> bool {
+        self.base.is_none()
+    }
+}
+
+/// The natural logarithm.
+pub fn log(arg: impl Into<Expr>) -> Expr<Log> {
+    Expr::new(Log::new(arg.into(), None))
+}
+
+pub fn log_base(arg: impl Into<Expr>, base: impl Into<Expr>) -> Expr<Log> {
+    Expr::new(Log::new(arg.into(), Some(base.into())))
+}
+
+impl PartialEq for Log {
+    fn eq(&self, other: &Log) -> bool {
+        PartialEq::eq(&self.arg, &other.arg) && self.base == other.base
+    }
+}
+
+impl Basic for Log {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_log(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        if let Some(other) = other.downcast::<Self>() {
+            self == other
+        } else {
+            false
+        }
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+
+    fn args(&self) -> Vec<Expr> {
+        let mut args = vec![self.arg.clone()];
+        args.extend(self.base.clone());
+        args
+    }
+
+    fn with_args(&self, args: Vec<Expr>) -> Option<Expr> {
+        let mut args = args.into_iter();
+        let arg = args.next()?;
+        Some(Expr::new(Log::new(arg, args.next())))
+    }
+}
@@ -71,4 +71,12 @@ impl Basic for Plus {
     fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
         other.downcast::<Self>().map(|other| Ord::cmp(self, other))
     }
+
+    fn args(&self) -> Vec<Expr> {
+        self.terms.clone()
+    }
+
+    fn with_args(&self, args: Vec<Expr>) -> Option<Expr> {
+        Some(Expr::new(Plus::new(args)))
+    }
 }
@@ -0,0 +1,71 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use crate::{Number, basic::Basic, expr::{Expr, ExprRef}, visitor::Visitor};
+
+/// This corresponds to `base ^ exp`
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
+pub struct Pow {
+    base: Expr,
+    exp: Expr,
+}
+
+impl Pow {
+    pub fn new(base: Expr, exp: Expr) -> Self {
+        Self {
+            base,
+            exp,
+        }
+    }
+
+    pub fn base(&self) -> ExprRef<'_> {
+        self.base.rf()
+    }
+
+    pub fn exp(&self) -> ExprRef<'_> {
+        self.exp.rf()
+    }
+}
+
+pub fn pow(base: ExprRef, exp: ExprRef) -> Expr {
+    if let (Some(base), Some(exp)) = (base.downcast::<Number>(), exp.downcast::<Number>()) {
+        if let Some(n) = base.pow(exp) {
+            return Expr::new(n);
+        }
+    }
+
+    Expr::new(Pow::new(base.into_expr(), exp.into_expr()))
+}
+
+impl PartialEq for Pow {
+    fn eq(&self, other: &Pow) -> bool {
+        PartialEq::eq(&self.base, &other.base) && PartialEq::eq(&self.exp, &other.exp)
+    }
+}
+
+impl Basic for Pow {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_pow(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        if let Some(other) = other.downcast::<Self>() {
+            self == other
+        } else {
+            false
+        }
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+
+    fn args(&self) -> Vec<Expr> {
+        vec![self.base.clone(), self.exp.clone()]
+    }
+
+    fn with_args(&self, args: Vec<Expr>) -> Option<Expr> {
+        let mut args = args.into_iter();
+        let (base, exp) = (args.next()?, args.next()?);
+        Some(Expr::new(Pow::new(base, exp)))
+    }
+}
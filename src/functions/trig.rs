@@ -0,0 +1,413 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use num::{BigInt, BigRational, One, Signed, ToPrimitive, Zero};
+
+use crate::{Number, basic::Basic, canonicalize::canonicalize, constant::{Constant, PI}, expr::{Expr, ExprRef}, undefined::UNDEFINED, visitor::Visitor};
+
+use super::{Pow, Times};
+
+unary_function!(
+    /// This corresponds to `sin(arg)`
+    Sin, sin, visit_sin
+);
+unary_function!(
+    /// This corresponds to `cos(arg)`
+    Cos, cos, visit_cos
+);
+unary_function!(
+    /// This corresponds to `tan(arg)`
+    Tan, tan, visit_tan
+);
+unary_function!(
+    /// This corresponds to `cot(arg)`
+    Cot, cot, visit_cot
+);
+unary_function!(
+    /// This corresponds to `sec(arg)`
+    Sec, sec, visit_sec
+);
+unary_function!(
+    /// This corresponds to `csc(arg)`
+    Csc, csc, visit_csc
+);
+unary_function!(
+    /// This corresponds to `asin(arg)`
+    Asin, asin, visit_asin
+);
+unary_function!(
+    /// This corresponds to `acos(arg)`
+    Acos, acos, visit_acos
+);
+unary_function!(
+    /// This corresponds to `atan(arg)`
+    Atan, atan, visit_atan
+);
+
+/// This corresponds to `atan2(y, x)`, the angle of the point `(x, y)`.
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
+pub struct Atan2 {
+    y: Expr,
+    x: Expr,
+}
+
+impl Atan2 {
+    pub fn new(y: Expr, x: Expr) -> Self {
+        Self {
+            y,
+            x,
+        }
+    }
+
+    pub fn y(&self) -> ExprRef<'_> {
+        self.y.rf()
+    }
+
+    pub fn x(&self) -> ExprRef<'_> {
+        self.x.rf()
+    }
+}
+
+pub fn atan2(y: impl Into<Expr>, x: impl Into<Expr>) -> Expr<Atan2> {
+    Expr::new(Atan2::new(y.into(), x.into()))
+}
+
+impl PartialEq for Atan2 {
+    fn eq(&self, other: &Atan2) -> bool {
+        PartialEq::eq(&self.y, &other.y) && PartialEq::eq(&self.x, &other.x)
+    }
+}
+
+impl Basic for Atan2 {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_atan2(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        if let Some(other) = other.downcast::<Self>() {
+            self == other
+        } else {
+            false
+        }
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+
+    fn args(&self) -> Vec<Expr> {
+        vec![self.y.clone(), self.x.clone()]
+    }
+
+    fn with_args(&self, args: Vec<Expr>) -> Option<Expr> {
+        let mut args = args.into_iter();
+        let (y, x) = (args.next()?, args.next()?);
+        Some(Expr::new(Atan2::new(y, x)))
+    }
+}
+
+// Exact values. These expect the arguments to already be canonical.
+
+impl Sin {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if let Some(asin) = self.arg.downcast::<Asin>() {
+            return Some(asin.arg().into_expr());
+        }
+
+        pi_coefficient(&self.arg).and_then(|r| exact_sin(&r)).map(|s| s.to_expr())
+    }
+}
+
+impl Cos {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if let Some(acos) = self.arg.downcast::<Acos>() {
+            return Some(acos.arg().into_expr());
+        }
+
+        pi_coefficient(&self.arg).and_then(|r| exact_cos(&r)).map(|s| s.to_expr())
+    }
+}
+
+impl Tan {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        if let Some(atan) = self.arg.downcast::<Atan>() {
+            return Some(atan.arg().into_expr());
+        }
+
+        let r = pi_coefficient(&self.arg)?;
+        Surd::ratio(exact_sin(&r)?, exact_cos(&r)?)
+    }
+}
+
+impl Cot {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        let r = pi_coefficient(&self.arg)?;
+        Surd::ratio(exact_cos(&r)?, exact_sin(&r)?)
+    }
+}
+
+impl Sec {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        let r = pi_coefficient(&self.arg)?;
+        Surd::ratio(Surd::rational(BigRational::one()), exact_cos(&r)?)
+    }
+}
+
+impl Csc {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        let r = pi_coefficient(&self.arg)?;
+        Surd::ratio(Surd::rational(BigRational::one()), exact_sin(&r)?)
+    }
+}
+
+impl Asin {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        exact_asin(&Surd::from_expr(&self.arg)?).map(pi_multiple)
+    }
+}
+
+impl Acos {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        // acos(x) = pi/2 - asin(x)
+        exact_asin(&Surd::from_expr(&self.arg)?)
+            .map(|r| pi_multiple(BigRational::new(1.into(), 2.into()) - r))
+    }
+}
+
+impl Atan {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        exact_atan(&Surd::from_expr(&self.arg)?).map(pi_multiple)
+    }
+}
+
+impl Atan2 {
+    pub fn eval_exact(&self) -> Option<Expr> {
+        let y = Surd::from_expr(&self.y)?;
+        let x = Surd::from_expr(&self.x)?;
+
+        let half = BigRational::new(1.into(), 2.into());
+        let r = if x.coef.is_zero() {
+            match y.coef.signum() {
+                s if s.is_positive() => half,
+                s if s.is_negative() => -half,
+                _ => return Some(UNDEFINED.clone()),
+            }
+        } else {
+            let r = exact_atan(&y.div(&x)?)?;
+            if x.coef.is_positive() {
+                r
+            } else if y.coef.is_negative() {
+                r - BigRational::one()
+            } else {
+                r + BigRational::one()
+            }
+        };
+
+        Some(pi_multiple(r))
+    }
+}
+
+/// `coef * sqrt(radicand)`, which is enough to write the values
+/// of the trigonometric functions at the standard angles.
+#[derive(Debug, Clone, PartialEq)]
+struct Surd {
+    coef: BigRational,
+    radicand: u32,
+}
+
+impl Surd {
+    fn new(coef: BigRational, mut radicand: u32) -> Self {
+        let mut coef = coef;
+        if radicand == 0 {
+            return Self::rational(BigRational::zero());
+        }
+
+        // pull square factors out of the radical
+        let mut factor = 2;
+        while factor * factor <= radicand {
+            while radicand.is_multiple_of(factor * factor) {
+                radicand /= factor * factor;
+                coef *= BigRational::from_integer(factor.into());
+            }
+            factor += 1;
+        }
+
+        Self {
+            coef,
+            radicand,
+        }
+    }
+
+    fn rational(coef: BigRational) -> Self {
+        Self {
+            coef,
+            radicand: 1,
+        }
+    }
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        if let Some(n) = expr.downcast::<Number>() {
+            return Some(Self::rational(n.to_rational()?));
+        }
+
+        let sqrt_of = |expr: &Expr| -> Option<u32> {
+            let pow = expr.downcast::<Pow>()?;
+            let half = Number::rational(1, 2);
+            if pow.exp().downcast::<Number>()? != &half {
+                return None;
+            }
+            match pow.base().downcast::<Number>()? {
+                Number::Integer(i) => i.to_u32(),
+                _ => None,
+            }
+        };
+
+        if let Some(radicand) = sqrt_of(expr) {
+            return Some(Self::new(BigRational::one(), radicand));
+        }
+
+        let times = expr.downcast::<Times>()?;
+        match times.terms() {
+            [a, b] => {
+                let (coef, radical) = if a.is::<Number>() { (a, b) } else { (b, a) };
+                let coef = coef.downcast::<Number>()?.to_rational()?;
+                Some(Self::new(coef, sqrt_of(radical)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn neg(&self) -> Self {
+        Self {
+            coef: -self.coef.clone(),
+            radicand: self.radicand,
+        }
+    }
+
+    fn div(&self, other: &Surd) -> Option<Self> {
+        if other.coef.is_zero() {
+            return None;
+        }
+
+        // a*sqrt(n) / (b*sqrt(m)) = a / (b*m) * sqrt(n*m)
+        let coef = &self.coef / (&other.coef * BigRational::from_integer(other.radicand.into()));
+        Some(Self::new(coef, self.radicand.checked_mul(other.radicand)?))
+    }
+
+    /// `numer / denom`, which is undefined when `denom` is zero.
+    fn ratio(numer: Surd, denom: Surd) -> Option<Expr> {
+        Some(match numer.div(&denom) {
+            Some(s) => s.to_expr(),
+            None => UNDEFINED.clone(),
+        })
+    }
+
+    fn to_expr(&self) -> Expr {
+        let coef: Expr = Expr::new(Number::from_rational(self.coef.clone()));
+        if self.radicand == 1 {
+            coef
+        } else {
+            let half: Expr = Expr::new(Number::rational(1, 2));
+            canonicalize(coef * Expr::from(self.radicand).pow(half))
+        }
+    }
+}
+
+/// If `expr` is a rational multiple of pi, returns that multiple.
+fn pi_coefficient(expr: &Expr) -> Option<BigRational> {
+    if expr.downcast::<Number>().is_some_and(Number::is_zero) {
+        return Some(BigRational::zero());
+    }
+
+    if expr.downcast::<Constant>() == Some(&Constant::Pi) {
+        return Some(BigRational::one());
+    }
+
+    let times = expr.downcast::<Times>()?;
+    match times.terms() {
+        [a, b] => {
+            let (coef, pi) = if a.is::<Number>() { (a, b) } else { (b, a) };
+            if pi.downcast::<Constant>() == Some(&Constant::Pi) {
+                coef.downcast::<Number>()?.to_rational()
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn pi_multiple(r: BigRational) -> Expr {
+    canonicalize(Expr::new(Number::from_rational(r)) * PI.clone())
+}
+
+/// Reduces `r` into `[0, period)`.
+fn reduce(r: &BigRational, period: i64) -> BigRational {
+    let period = BigRational::from_integer(BigInt::from(period));
+    r - (r / &period).floor() * period
+}
+
+/// `sin(r * pi)` for the multiples of `pi/6` and `pi/4`.
+fn exact_sin(r: &BigRational) -> Option<Surd> {
+    let mut r = reduce(r, 2);
+    let mut negate = false;
+
+    if r >= BigRational::one() {
+        r -= BigRational::one();
+        negate = true;
+    }
+
+    let half = BigRational::new(1.into(), 2.into());
+    if r > half {
+        r = BigRational::one() - r;
+    }
+
+    let value = sin_table(&r)?;
+    Some(if negate { value.neg() } else { value })
+}
+
+/// `cos(r * pi)`, using `cos(x) = sin(pi/2 - x)`.
+fn exact_cos(r: &BigRational) -> Option<Surd> {
+    exact_sin(&(BigRational::new(1.into(), 2.into()) - r))
+}
+
+/// `sin(r * pi)` for `r` in `[0, 1/2]`.
+fn sin_table(r: &BigRational) -> Option<Surd> {
+    let half = || BigRational::new(1.into(), 2.into());
+    let (numer, denom) = (r.numer().to_i64()?, r.denom().to_i64()?);
+
+    Some(match (numer, denom) {
+        (0, _) => Surd::rational(BigRational::zero()),
+        (1, 6) => Surd::rational(half()),
+        (1, 4) => Surd::new(half(), 2),
+        (1, 3) => Surd::new(half(), 3),
+        (1, 2) => Surd::rational(BigRational::one()),
+        _ => return None,
+    })
+}
+
+/// The `r` for which `asin(value) = r * pi`.
+fn exact_asin(value: &Surd) -> Option<BigRational> {
+    if value.coef.is_negative() {
+        return exact_asin(&value.neg()).map(|r| -r);
+    }
+
+    (0..=3)
+        .map(|k| BigRational::new(k.into(), 6.into()))
+        .chain([BigRational::new(1.into(), 4.into())])
+        .find(|r| sin_table(r).as_ref() == Some(value))
+}
+
+/// The `r` for which `atan(value) = r * pi`.
+fn exact_atan(value: &Surd) -> Option<BigRational> {
+    if value.coef.is_negative() {
+        return exact_atan(&value.neg()).map(|r| -r);
+    }
+
+    [(0, 1), (1, 6), (1, 4), (1, 3)]
+        .iter()
+        .map(|&(n, d)| BigRational::new(n.into(), d.into()))
+        .find(|r| {
+            let tan = exact_sin(r).zip(exact_cos(r)).and_then(|(s, c)| s.div(&c));
+            tan.as_ref() == Some(value)
+        })
+}
@@ -0,0 +1,298 @@
+use std::collections::BTreeSet;
+
+use crate::{Number, expr::Expr, polys::{MonomialOrder, Poly, PolyError}};
+
+/// Which algorithm [`groebner_with`] uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Method {
+    /// Reduces one S-polynomial at a time, smallest lcm first, skipping
+    /// pairs by Buchberger's criteria.
+    #[default]
+    Buchberger,
+    /// Reduces all the S-polynomials of the lowest degree at once, as
+    /// the rows of one matrix.
+    F4,
+}
+
+/// The reduced Gröbner basis of a polynomial ideal, with monic
+/// polynomials sorted by leading monomial, largest first.
+#[derive(Debug, Clone)]
+pub struct GroebnerBasis {
+    gens: Vec<Expr>,
+    order: MonomialOrder,
+    polys: Vec<Poly>,
+}
+
+impl GroebnerBasis {
+    pub fn gens(&self) -> &[Expr] {
+        &self.gens
+    }
+
+    pub fn order(&self) -> MonomialOrder {
+        self.order
+    }
+
+    pub fn polys(&self) -> &[Poly] {
+        &self.polys
+    }
+
+    pub fn exprs(&self) -> Vec<Expr> {
+        self.polys.iter().map(Poly::to_expr).collect()
+    }
+
+    /// Whether the ideal is the whole ring, so the polynomials have no
+    /// common zero.
+    pub fn is_trivial(&self) -> bool {
+        self.polys.iter().any(|p| p.total_degree() == Some(0))
+    }
+
+    /// The remainder of `expr` on division by the basis, which is the
+    /// same for every member of a class modulo the ideal.
+    ///
+    /// Fails when `expr` is not a polynomial in the generators.
+    pub fn reduce(&self, expr: &Expr) -> Result<Expr, PolyError> {
+        let p = Poly::from_expr(expr, &self.gens)?.with_order(self.order);
+        Ok(normal_form(&p, &self.polys).to_expr())
+    }
+
+    /// Whether `expr` is in the ideal, which is when it reduces to zero.
+    pub fn contains(&self, expr: &Expr) -> bool {
+        Poly::from_expr(expr, &self.gens).is_ok_and(|p| normal_form(&p.with_order(self.order), &self.polys).is_zero())
+    }
+}
+
+/// The reduced Gröbner basis of the ideal generated by `polys` in the
+/// generators `gens`, or in all of their generators when `gens` is empty.
+///
+/// With the `Lex` order, the basis elements that don't have the first
+/// `k` generators generate the elimination ideal of those, which is
+/// how polynomial systems are solved one variable at a time.
+pub fn groebner(polys: &[Expr], gens: &[Expr], order: MonomialOrder) -> Result<GroebnerBasis, PolyError> {
+    groebner_with(polys, gens, order, Method::default())
+}
+
+/// See [`groebner`] and [`Method`].
+pub fn groebner_with(polys: &[Expr], gens: &[Expr], order: MonomialOrder, method: Method) -> Result<GroebnerBasis, PolyError> {
+    let polys = Poly::from_exprs(polys, gens)?;
+    let gens = match polys.first() {
+        Some(p) => p.gens().to_vec(),
+        None => gens.to_vec(),
+    };
+
+    let polys: Vec<Poly> = polys.into_iter().filter(|p| !p.is_zero()).map(|p| p.with_order(order).monic()).collect();
+    let basis = match method {
+        Method::Buchberger => buchberger(polys),
+        Method::F4 => f4(polys),
+    };
+
+    Ok(GroebnerBasis {
+        gens,
+        order,
+        polys: reduced(basis, order),
+    })
+}
+
+/// Reduces `expr` modulo the side relations `relations`, each meaning
+/// that it equals zero, so `x^2 + y^2 + x` modulo `x^2 + y^2 - 1`
+/// is `x + 1`.
+///
+/// The result is the remainder on division by the `Grevlex` Gröbner
+/// basis of the relations, in the generators of everything together.
+/// `expr` is returned as it is when something isn't a polynomial.
+pub fn reduce(expr: &Expr, relations: &[Expr]) -> Expr {
+    let mut exprs = vec![expr.clone()];
+    exprs.extend(relations.iter().cloned());
+    let gens = match Poly::from_exprs(&exprs, &[]) {
+        Ok(polys) => polys[0].gens().to_vec(),
+        Err(_) => return expr.clone(),
+    };
+
+    groebner(relations, &gens, MonomialOrder::Grevlex)
+        .and_then(|basis| basis.reduce(expr))
+        .unwrap_or_else(|_| expr.clone())
+}
+
+fn leading_monomial(p: &Poly) -> Vec<u32> {
+    p.leading_term().unwrap().0.to_vec()
+}
+
+fn lcm(a: &[u32], b: &[u32]) -> Vec<u32> {
+    a.iter().zip(b).map(|(a, b)| *a.max(b)).collect()
+}
+
+fn divides(a: &[u32], b: &[u32]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a <= b)
+}
+
+/// `c * gens^exps` in the generators and order of `like`.
+fn monomial(like: &Poly, exps: Vec<u32>, c: Number) -> Poly {
+    Poly::from_terms(like.gens().to_vec(), [(exps, c)]).with_order(like.order())
+}
+
+/// `p * lcm / lm(p)`, for a monic `p`.
+fn lift(p: &Poly, lcm: &[u32]) -> Poly {
+    let shift = lcm.iter().zip(leading_monomial(p)).map(|(l, e)| l - e).collect();
+    p * &monomial(p, shift, Number::Integer(1.into()))
+}
+
+fn s_polynomial(f: &Poly, g: &Poly) -> Poly {
+    let lcm = lcm(&leading_monomial(f), &leading_monomial(g));
+    lift(f, &lcm) - lift(g, &lcm)
+}
+
+/// The remainder of `p` on division by the monic `basis`, where no
+/// term is divisible by a leading monomial of `basis`.
+fn normal_form(p: &Poly, basis: &[Poly]) -> Poly {
+    let leads: Vec<Vec<u32>> = basis.iter().map(leading_monomial).collect();
+    let mut p = p.clone();
+    let mut rem = Poly::zero(p.gens().to_vec()).with_order(p.order());
+    while let Some((exps, c)) = p.leading_term() {
+        let (exps, c) = (exps.to_vec(), c.clone());
+        match leads.iter().position(|lead| divides(lead, &exps)) {
+            Some(i) => {
+                let shift = exps.iter().zip(&leads[i]).map(|(e, l)| e - l).collect();
+                let factor = monomial(&p, shift, c);
+                p = p - &basis[i] * &factor;
+            }
+            None => {
+                let term = monomial(&p, exps, c);
+                p = p - &term;
+                rem = rem + term;
+            }
+        }
+    }
+    rem
+}
+
+/// Whether the pair `(i, j)` can be skipped because their leading
+/// monomials are coprime, or because some other element's leading
+/// monomial divides their lcm and its pairs with both were already done.
+fn skip_pair(basis: &[Poly], pairs: &[(usize, usize)], (i, j): (usize, usize)) -> bool {
+    let (a, b) = (leading_monomial(&basis[i]), leading_monomial(&basis[j]));
+    if a.iter().zip(&b).all(|(a, b)| *a == 0 || *b == 0) {
+        return true;
+    }
+
+    let lcm = lcm(&a, &b);
+    let pending = |x: usize, y: usize| pairs.contains(&(x.min(y), x.max(y)));
+    (0..basis.len()).any(|k| k != i && k != j && divides(&leading_monomial(&basis[k]), &lcm) && !pending(i, k) && !pending(j, k))
+}
+
+fn buchberger(mut basis: Vec<Poly>) -> Vec<Poly> {
+    let mut pairs: Vec<(usize, usize)> = (0..basis.len()).flat_map(|j| (0..j).map(move |i| (i, j))).collect();
+    while !pairs.is_empty() {
+        let lcm_of = |&(i, j): &(usize, usize)| lcm(&leading_monomial(&basis[i]), &leading_monomial(&basis[j]));
+        let order = basis[0].order();
+        let k = (0..pairs.len()).min_by(|&a, &b| order.cmp(&lcm_of(&pairs[a]), &lcm_of(&pairs[b]))).unwrap();
+        let pair = pairs.remove(k);
+        if skip_pair(&basis, &pairs, pair) {
+            continue;
+        }
+
+        let s = normal_form(&s_polynomial(&basis[pair.0], &basis[pair.1]), &basis);
+        if !s.is_zero() {
+            let n = basis.len();
+            basis.push(s.monic());
+            pairs.extend((0..n).map(|i| (i, n)));
+        }
+    }
+    basis
+}
+
+fn f4(mut basis: Vec<Poly>) -> Vec<Poly> {
+    let mut pairs: Vec<(usize, usize)> = (0..basis.len()).flat_map(|j| (0..j).map(move |i| (i, j))).collect();
+    while !pairs.is_empty() {
+        let lcm_of = |&(i, j): &(usize, usize)| lcm(&leading_monomial(&basis[i]), &leading_monomial(&basis[j]));
+        let degree = pairs.iter().map(|pair| lcm_of(pair).iter().sum::<u32>()).min().unwrap();
+        let (selected, rest): (Vec<_>, Vec<_>) = pairs.into_iter().partition(|pair| lcm_of(pair).iter().sum::<u32>() == degree);
+        pairs = rest;
+
+        // pairs of the same degree count as pending, so they can't be
+        // skipped because of each other
+        let pending: Vec<(usize, usize)> = pairs.iter().chain(&selected).copied().collect();
+        let mut rows: Vec<Poly> = vec![];
+        for (i, j) in selected {
+            if skip_pair(&basis, &pending, (i, j)) {
+                continue;
+            }
+            let lcm = lcm_of(&(i, j));
+            rows.push(lift(&basis[i], &lcm));
+            rows.push(lift(&basis[j], &lcm));
+        }
+        if rows.is_empty() {
+            continue;
+        }
+
+        // symbolic preprocessing: add a multiple of the basis for every
+        // monomial of the rows that some leading monomial divides
+        let mut done: BTreeSet<Vec<u32>> = rows.iter().map(leading_monomial).collect();
+        let mut k = 0;
+        while k < rows.len() {
+            let monomials: Vec<Vec<u32>> = rows[k].terms().into_iter().map(|(e, _)| e.to_vec()).collect();
+            for m in monomials {
+                if !done.insert(m.clone()) {
+                    continue;
+                }
+                if let Some(g) = basis.iter().find(|g| divides(&leading_monomial(g), &m)) {
+                    rows.push(lift(g, &m));
+                }
+            }
+            k += 1;
+        }
+
+        // row reduce, keeping the rows with new leading monomials
+        let leads: BTreeSet<Vec<u32>> = rows.iter().map(leading_monomial).collect();
+        let mut pivots: Vec<Poly> = vec![];
+        for row in rows {
+            let row = normal_form_partial(&row, &pivots);
+            if !row.is_zero() {
+                pivots.push(row.monic());
+            }
+        }
+
+        for row in pivots.into_iter().filter(|row| !leads.contains(&leading_monomial(row))) {
+            let n = basis.len();
+            basis.push(row);
+            pairs.extend((0..n).map(|i| (i, n)));
+        }
+    }
+    basis
+}
+
+/// Eliminates from `row` every monomial that is the leading monomial
+/// of one of the monic `pivots`, which is one step of Gaussian
+/// elimination on the rows.
+fn normal_form_partial(row: &Poly, pivots: &[Poly]) -> Poly {
+    let mut row = row.clone();
+    for pivot in pivots {
+        let lead = leading_monomial(pivot);
+        let c = row.coeff(&lead);
+        if !c.is_zero() {
+            row = row - pivot.scale(&c);
+        }
+    }
+    row
+}
+
+/// Turns a Gröbner basis into the reduced one, dropping the elements
+/// whose leading monomial is a multiple of another's and reducing each
+/// of the rest by the others.
+fn reduced(mut basis: Vec<Poly>, order: MonomialOrder) -> Vec<Poly> {
+    basis.sort_by(|a, b| order.cmp(&leading_monomial(a), &leading_monomial(b)));
+    let mut minimal: Vec<Poly> = vec![];
+    for p in basis {
+        let lead = leading_monomial(&p);
+        if !minimal.iter().any(|q| divides(&leading_monomial(q), &lead)) {
+            minimal.push(p);
+        }
+    }
+
+    let mut res: Vec<Poly> = (0..minimal.len())
+        .map(|i| {
+            let others: Vec<Poly> = minimal.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, q)| q.clone()).collect();
+            normal_form(&minimal[i], &others).monic()
+        })
+        .collect();
+    res.reverse();
+    res
+}
@@ -1,28 +1,49 @@
 #![feature(unsize, coerce_unsized, dispatch_from_dyn, arbitrary_self_types)]
 #![deny(unsafe_op_in_unsafe_fn)]
 
-mod basic;
-mod number;
-// mod assumptions;
-mod symbol;
-mod functions;
+pub mod basic;
+pub mod number;
+pub mod constant;
+pub mod assumptions;
+pub mod symbol;
+pub mod pattern;
+pub mod functions;
 // mod diff;
-mod visitor;
-mod undefined;
-mod expr;
-// mod simplify;
-mod eval_symbolic;
-mod canonicalize;
-mod convert;
-// mod simplify;
+pub mod visitor;
+pub mod undefined;
+pub mod expr;
+pub mod simplify;
+pub mod eval_symbolic;
+pub mod canonicalize;
+pub mod convert;
+pub mod fold;
+pub mod rewrite;
+pub mod subs;
+pub mod rules;
+pub mod expand;
+pub mod factor;
+pub mod polys;
+pub mod rational;
+pub mod groebner;
+pub mod resultant;
+pub mod roots;
+pub mod real_roots;
+pub mod collect;
 // mod units;
-// mod expr;
 
 pub use self::number::{Number, ZERO, ONE, MINUS_ONE};
+pub use self::basic::Basic;
+pub use self::expr::{Expr, ExprRef};
+pub use self::visitor::Visitor;
+pub use self::functions::{exp, log, log_base, sin, cos, tan, cot, sec, csc, asin, acos, atan, atan2, sinh, cosh, tanh, asinh, acosh, atanh, apply};
 
 #[cfg(test)]
 mod tests {
-    use crate::{convert::{Convert, LaTex}, eval_symbolic::symbolic_eval, functions::derivative, symbol::sym};
+    use num::{Signed, ToPrimitive};
+
+    use std::{cmp::Ordering, ops::ControlFlow};
+
+    use crate::{MINUS_ONE, ONE, ZERO, basic::Basic, expr::ExprRef, fold::{Fold, Transformer, bottom_up, fold, top_down}, symbol::depends_on, visitor::Visitor, canonicalize::canonicalize, convert::{Convert, LaTex}, eval_symbolic::symbolic_eval, Number, constant::{Constant, E, EULER_GAMMA, I, PI}, expr::Expr, functions::{AppliedFunction, Log, Plus, Times, apply, asin, asinh, atan2, cos, cosh, derivative, exp, log, sin, sinh, tan, tanh}, pattern::{WildPredicate, match_pattern, wild, wild_where}, expand::{ExpandOptions, expand, expand_with}, factor::{factor, factor_list}, polys::{MonomialOrder, Poly, PolyError}, rational::{apart, cancel, fraction, gcd, together}, groebner::{Method, groebner, groebner_with, reduce}, resultant::{ResultantMethod, discriminant, resultant, resultant_with}, roots::{RootOf, roots}, real_roots::{RefineError, count_real_roots, real_root_intervals, refine_root_interval}, collect::{coeff, collect, degree, horner, leading_term}, rewrite::{Rewrite, rewrite}, rules::{Rule, RuleSet, Stop}, simplify::{CostFunction, collect_like_terms, simplify, simplify_with}, subs::subs_simultaneous, symbol::{real_sym, sym}};
     
     #[test]
     fn it_works() {
@@ -35,4 +56,852 @@ mod tests {
 
         println!("{}", LaTex::convert_to_string(a));
     }
+
+    #[test]
+    fn merges_repeated_factors_into_powers() {
+        let x = sym("x");
+        let a = sym("a");
+        let b = sym("b");
+
+        let cubed = canonicalize(x.clone() * x.clone() * x.clone());
+        assert_eq!(&cubed, &canonicalize(x.clone().pow(3)));
+        assert_eq!(LaTex::convert_to_string(cubed), "x^{3}");
+
+        let merged = canonicalize(x.clone().pow(a.clone()) * x.clone().pow(b.clone()));
+        assert_eq!(&merged, &canonicalize(x.clone().pow(a.clone() + b.clone())));
+
+        let prime = symbolic_eval(derivative(x.clone().pow(3), x.clone()));
+        assert_eq!(&prime, &canonicalize(x.clone().pow(2) * 3));
+
+        let exponential = symbolic_eval(derivative(a.clone().pow(x.clone()), x.clone()));
+        assert_eq!(&exponential, &canonicalize(a.clone().pow(x.clone()) * log(&a)));
+    }
+
+    #[test]
+    fn operator_algebra() {
+        let x = sym("x");
+        let y = sym("y");
+
+        assert_eq!(&canonicalize(&x - &y), &canonicalize(&x + (-1 * &y)));
+        assert_eq!(&canonicalize(-(-&x)), &canonicalize(x.clone().into()));
+        assert_eq!(&canonicalize(&x / &x), &canonicalize(1.into()));
+        assert_eq!(&canonicalize(2 * &x / 2), &canonicalize(x.clone().into()));
+        assert_eq!(&canonicalize(1.5 + &y - 0.5), &canonicalize(&y + 1));
+        assert_eq!(LaTex::convert_to_string(canonicalize(&x / &y)), LaTex::convert_to_string(canonicalize(y.clone().pow(-1) * &x)));
+    }
+
+    #[test]
+    fn exp_and_log_special_values() {
+        let x = sym("x");
+        let t = real_sym("t");
+
+        assert_eq!(&symbolic_eval(exp(0).into()), &(ONE.clone() as Expr));
+        assert_eq!(&symbolic_eval(log(1).into()), &(ZERO.clone() as Expr));
+        assert_eq!(&symbolic_eval(log(exp(&t)).into()), &(t.clone() as Expr));
+        assert!(symbolic_eval(log(exp(&x)).into()).is::<Log>());
+
+        let prime = symbolic_eval(derivative(log(&x).into(), x.clone()));
+        assert_eq!(&prime, &canonicalize(x.clone().pow(-1)));
+        assert_eq!(LaTex::convert_to_string(log(&x).into()), "\\ln\\left(x\\right)");
+    }
+
+    #[test]
+    fn trig_exact_values_and_parity() {
+        let x = sym("x");
+        let half = || -> Expr { Expr::new(Number::rational(1, 2)) };
+
+        assert_eq!(&symbolic_eval(sin(half() * PI.clone() / 3).into()), &half());
+        assert_eq!(&symbolic_eval(cos(PI.clone() * 7 / 3).into()), &half());
+        assert_eq!(&symbolic_eval(tan(PI.clone() / 4).into()), &(ONE.clone() as Expr));
+        assert_eq!(&symbolic_eval(tan(PI.clone() / 3).into()), &canonicalize(Expr::from(3i32).pow(half())));
+        assert_eq!(&symbolic_eval(asin(half()).into()), &canonicalize(PI.clone() / 6));
+        assert_eq!(&symbolic_eval(atan2(-1, -1).into()), &canonicalize(PI.clone() * -3 / 4));
+
+        assert_eq!(&canonicalize(sin(-&x).into()), &canonicalize(-sin(&x)));
+        assert_eq!(&canonicalize(cos(-2 * &x).into()), &canonicalize(cos(2 * &x).into()));
+
+        let prime = symbolic_eval(derivative(sin(&x).into(), x.clone()));
+        assert_eq!(&prime, &(cos(&x) as Expr));
+        assert_eq!(LaTex::convert_to_string(sin(&x).into()), "\\sin\\left(x\\right)");
+    }
+
+    #[test]
+    fn constants() {
+        let within = |c: Constant, digits: u32, expected: &str| {
+            let approx = c.approximate(digits).unwrap();
+            let expected: num::BigRational = format!("{}/1{}", expected.replace('.', ""), "0".repeat(expected.len() - 2))
+                .parse()
+                .unwrap();
+            let tolerance = num::BigRational::new(1.into(), num::pow(num::BigInt::from(10), digits as usize));
+            assert!((approx - expected).abs() < tolerance, "{:?}", c);
+        };
+
+        within(Constant::Pi, 45, "3.14159265358979323846264338327950288419716939937510");
+        within(Constant::E, 45, "2.71828182845904523536028747135266249775724709369995");
+        within(Constant::EulerGamma, 45, "0.57721566490153286060651209008240243104215933593992");
+        assert!(Constant::I.approximate(10).is_none());
+
+        assert_eq!(&canonicalize(I.clone() * I.clone()), &(MINUS_ONE.clone() as Expr));
+        assert_eq!(&symbolic_eval(log(E.clone()).into()), &(ONE.clone() as Expr));
+        assert_eq!(LaTex::convert_to_string(PI.clone() * EULER_GAMMA.clone()), "\\pi * \\gamma");
+    }
+
+    #[test]
+    fn hyperbolic_functions() {
+        let x = sym("x");
+
+        assert_eq!(&symbolic_eval(sinh(0).into()), &(ZERO.clone() as Expr));
+        assert_eq!(&symbolic_eval(cosh(0).into()), &(ONE.clone() as Expr));
+        assert_eq!(&canonicalize(sinh(-&x).into()), &canonicalize(-sinh(&x)));
+        assert_eq!(&canonicalize(cosh(-&x).into()), &canonicalize(cosh(&x).into()));
+
+        let prime = symbolic_eval(derivative(sinh(&x).into(), x.clone()));
+        assert_eq!(&prime, &(cosh(&x) as Expr));
+        assert_eq!(LaTex::convert_to_string(tanh(&x).into()), "\\tanh\\left(x\\right)");
+
+        assert_eq!(&rewrite(sinh(&x).into(), Rewrite::ExpLog), &canonicalize((exp(&x) - exp(-&x)) / 2));
+        assert_eq!(&rewrite(exp(&x).into(), Rewrite::Hyperbolic), &canonicalize(cosh(&x) + sinh(&x)));
+        let asinh_log = rewrite(asinh(&x).into(), Rewrite::ExpLog);
+        assert_eq!(&rewrite(asinh_log, Rewrite::Hyperbolic), &(asinh(&x) as Expr));
+    }
+
+    #[test]
+    fn applied_function_chain_rule() {
+        let x = sym("x");
+        let y = sym("y");
+        let f = apply("f", [&x]);
+
+        let f_prime: Expr = derivative(f.clone().into(), x.clone()).into();
+        assert_eq!(&symbolic_eval(f_prime.clone()), &f_prime);
+
+        let f_second: Expr = derivative(f_prime.clone(), x.clone()).into();
+        assert_eq!(&symbolic_eval(f_second.clone()), &f_second);
+
+        let prime = symbolic_eval(derivative(sin(&f).into(), x.clone()));
+        assert_eq!(&prime, &canonicalize(cos(&f) * &f_prime));
+
+        let x_squared = canonicalize(x.clone().pow(2));
+        let f_of_square = apply("f", [&x_squared]);
+        let partial = Expr::new(AppliedFunction::with_partials("f", vec![x_squared.clone()], vec![1]).unwrap());
+        assert!(AppliedFunction::with_partials("f", vec![x_squared.clone()], vec![1, 0]).is_none());
+        let prime = symbolic_eval(derivative(f_of_square.into(), x.clone()));
+        assert_eq!(&prime, &canonicalize(&partial * 2 * &x));
+        assert_eq!(LaTex::convert_to_string(partial), "f'\\left(x^{2}\\right)");
+
+        let g: Expr = apply("g", [&x, &y]).into();
+        let prime = symbolic_eval(derivative(g.clone(), y.clone()));
+        assert_eq!(&prime, &(derivative(g, y.clone()) as Expr));
+    }
+
+    /// The logistic function, defined outside of the built-in node types.
+    #[derive(Debug)]
+    struct Sigmoid(Expr);
+
+    impl Basic for Sigmoid {
+        fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+            visitor.visit_other(self)
+        }
+
+        fn eq(&self, other: &dyn Basic) -> bool {
+            other.downcast::<Self>().is_some_and(|other| PartialEq::eq(&self.0, &other.0))
+        }
+
+        fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+            other.downcast::<Self>().map(|other| Ord::cmp(&self.0, &other.0))
+        }
+
+        fn args(&self) -> Vec<Expr> {
+            vec![self.0.clone()]
+        }
+
+        fn with_args(&self, mut args: Vec<Expr>) -> Option<Expr> {
+            Some(Expr::new(Sigmoid(args.pop()?)))
+        }
+
+        fn partial_derivative(&self, _i: usize) -> Option<Expr> {
+            // s' = s * (1 - s)
+            let s = Expr::new(Sigmoid(self.0.clone()));
+            Some(&s * (-&s + 1))
+        }
+    }
+
+    #[test]
+    fn custom_nodes() {
+        let x = sym("x");
+        let y = sym("y");
+        let s: Expr = Expr::new(Sigmoid(x.clone().pow(2)));
+
+        assert!(depends_on(s.rf(), &x));
+        assert!(!depends_on(s.rf(), &y));
+        assert_eq!(&canonicalize(Expr::new(Sigmoid(&x * &x))), &canonicalize(s.clone()));
+        assert_eq!(LaTex::convert_to_string(canonicalize(s.clone())), "\\operatorname{Sigmoid}\\left(x^{2}\\right)");
+
+        let prime = symbolic_eval(derivative(s.clone(), x.clone()).into());
+        assert_eq!(&prime, &canonicalize(&s * (-&s + 1) * 2 * &x));
+        assert_eq!(&symbolic_eval(derivative(s, y.clone()).into()), &(ZERO.clone() as Expr));
+    }
+
+    #[test]
+    fn fold_and_transform() {
+        struct CountNodes;
+
+        impl Fold for CountNodes {
+            type Output = usize;
+
+            fn fold(&mut self, _expr: ExprRef, args: Vec<usize>) -> ControlFlow<(), usize> {
+                ControlFlow::Continue(1 + args.iter().sum::<usize>())
+            }
+        }
+
+        /// Replaces `sin(u)` with `cos(u)`, counting the replacements.
+        struct SinToCos(usize);
+
+        impl Transformer for SinToCos {
+            fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+                ControlFlow::Continue(match expr.downcast::<crate::functions::Sin>() {
+                    Some(sin) => {
+                        self.0 += 1;
+                        cos(sin.arg()).into()
+                    }
+                    None => expr,
+                })
+            }
+        }
+
+        let x = sym("x");
+        let y = sym("y");
+        let expr = canonicalize(&x * &y + 2);
+        assert_eq!(fold(expr.rf(), &mut CountNodes), ControlFlow::Continue(5));
+
+        let nested: Expr = sin(sin(&x) + &y).into();
+        let mut t = SinToCos(0);
+        assert_eq!(&bottom_up(nested.rf(), &mut t).continue_value().unwrap(), &(cos(cos(&x) + &y) as Expr));
+        assert_eq!(t.0, 2);
+
+        // top-down replaces the outer `sin` first, then visits the args of its replacement
+        let mut t = SinToCos(0);
+        assert_eq!(&top_down(nested.rf(), &mut t).continue_value().unwrap(), &(cos(cos(&x) + &y) as Expr));
+        assert_eq!(t.0, 2);
+    }
+
+    #[test]
+    fn substitution() {
+        let x: Expr = sym("x").into();
+        let y: Expr = sym("y").into();
+        let z: Expr = sym("z").into();
+
+        let expr: Expr = &x * &y + &x;
+        let rules: [(Expr, Expr); 2] = [(x.clone(), &y + 1), (y.clone(), 2.into())];
+        assert_eq!(&expr.subs(&rules), &canonicalize(9.into()));
+        assert_eq!(&subs_simultaneous(expr, &rules), &canonicalize((&y + 1) * 2 + &y + 1));
+
+        let swap: [(Expr, Expr); 2] = [(x.clone(), y.clone()), (y.clone(), x.clone())];
+        assert_eq!(&subs_simultaneous(&x + &y.clone().pow(2), &swap), &canonicalize(&y + x.clone().pow(2)));
+        assert_eq!(&(&x + &y).subs(&swap), &canonicalize(&x + &x));
+
+        let expr = &x + &y + sin(&z);
+        assert_eq!(&expr.subs(&[(&x + &y, z.clone())]), &canonicalize(&z + sin(&z)));
+        assert_eq!(&expr.subs(&[(&x + &z, 1.into())]), &canonicalize(expr.clone()));
+        assert_eq!(&sin(&x + &y + 1).subs(&[(&x + &y, PI.clone().into())]), &canonicalize(sin(PI.clone() + 1).into()));
+    }
+
+    #[test]
+    fn pattern_matching() {
+        let x = sym("x");
+        let y = sym("y");
+        let a = wild_where("a", [WildPredicate::Independent(x.clone())]);
+        let b = wild_where("b", [WildPredicate::Independent(x.clone())]);
+        let n = wild_where("n", [WildPredicate::Integer]);
+
+        let linear = &a * &x + &b;
+        let m = match_pattern(&linear, &(5 * &x + 3)).unwrap();
+        assert_eq!(m.get("a"), Some(&Expr::from(5i32)));
+        assert_eq!(m.get("b"), Some(&Expr::from(3i32)));
+
+        let m = match_pattern(&linear, &(&x * &y)).unwrap();
+        assert_eq!(m.get("a"), Some(&(y.clone() as Expr)));
+        assert_eq!(m.get("b"), Some(&(ZERO.clone() as Expr)));
+        assert!(match_pattern(&linear, &x.clone().pow(2)).is_none());
+
+        let m = match_pattern(&x.clone().pow(&n), &(x.clone() as Expr)).unwrap();
+        assert_eq!(m.get("n"), Some(&(ONE.clone() as Expr)));
+
+        // a repeated wild has to match the same thing each time
+        let w = wild("w");
+        let doubled_sin: Expr = sin(&w) * cos(&w);
+        assert!(match_pattern(&doubled_sin, &(sin(&x) * cos(&x))).is_some());
+        assert!(match_pattern(&doubled_sin, &(sin(&x) * cos(&y))).is_none());
+
+        let m = match_pattern(&(sin(&w) + 1), &(sin(&x * &y) + 1)).unwrap();
+        assert_eq!(&m.instantiate(cos(&w).into()), &canonicalize(cos(&x * &y).into()));
+
+        // lone wilds share a long sum without trying every split first
+        let long = canonicalize((0..40).fold(Expr::from(0i32), |sum, i| sum + sym(&format!("x{}", i))));
+        let m = match_pattern(&(wild("u") + wild("v")), &long).unwrap();
+        assert_eq!(&canonicalize(m.get("u").unwrap() + m.get("v").unwrap()), &long);
+        let (s, t) = (wild_where("s", [WildPredicate::Symbol]), wild_where("t", [WildPredicate::Symbol]));
+        assert!(match_pattern(&(&s + &t), &long).is_none());
+        let m = match_pattern(&(wild_where("u", [WildPredicate::Independent(sym("x7"))]) + &s), &long).unwrap();
+        assert_eq!(m.get("s"), Some(&(sym("x7") as Expr)));
+    }
+
+    #[test]
+    fn rule_sets() {
+        let x = sym("x");
+        let y = sym("y");
+        let u = wild("u");
+        let n = wild_where("n", [WildPredicate::Integer]);
+
+        // sin(u) * cos(u) -> sin(2u) / 2, and sin(n * pi) -> 0 for integers n
+        let rules = RuleSet::new([
+            Rule::new("double_angle", sin(&u) * cos(&u), sin(2 * &u) / 2),
+            Rule::new("sin_integer_pi", sin(&n * PI.clone()), ZERO.clone()),
+        ]);
+
+        let res = rules.apply(exp(sin(&x) * cos(&x)));
+        assert_eq!(res.stop, Stop::FixedPoint);
+        assert_eq!(&res.expr, &canonicalize(exp(sin(2 * &x) / 2).into()));
+        let fired: Vec<&str> = res.trace.iter().map(|step| step.rule.as_ref()).collect();
+        assert_eq!(fired, ["double_angle"]);
+
+        // conditions are checked against the bindings
+        let positive_only = RuleSet::new([Rule::new("drop_exp", exp(&n), n.clone())
+            .when(|b| b.get("n").and_then(|n| n.downcast::<Number>()).is_some_and(|n| n.is_positive()))]);
+        assert_eq!(&positive_only.apply(exp(2).into()).expr, &Expr::from(2i32));
+        assert_eq!(&positive_only.apply(exp(-2).into()).expr, &canonicalize(exp(-2).into()));
+
+        let swap = RuleSet::new([Rule::new("x_to_y", x.clone(), y.clone()), Rule::new("y_to_x", y.clone(), x.clone())]);
+        assert_eq!(swap.apply(x.clone().into()).stop, Stop::Cycle);
+
+        let f = |arg: Expr| -> Expr { apply("f", [arg]).into() };
+        let grow = RuleSet::new([Rule::new("nest", f(u.clone().into()), f(f(u.clone().into())))]).with_max_steps(5);
+        let res = grow.apply(f(x.clone().into()));
+        assert_eq!(res.stop, Stop::StepLimit);
+        assert_eq!(res.trace.len(), 5);
+    }
+
+    #[test]
+    fn equality_saturation() {
+        let x = sym("x");
+        let y = sym("y");
+        let z = sym("z");
+
+        let factored = canonicalize(&x * (&y + &z));
+        assert_eq!(&simplify(&x * &y + &x * &z), &factored);
+        assert_eq!(&simplify(&x * (&y + 1) - &x * &y), &(x.clone() as Expr));
+        assert_eq!(&simplify(x.clone().pow(2) * &y / &x), &canonicalize(&x * &y));
+        // has to get bigger before it gets smaller
+        assert_eq!(&simplify((&x + 1) * (&x - 1) - x.clone().pow(2)), &(MINUS_ONE.clone() as Expr));
+
+        // prefers sums of products over products of sums
+        struct Expanded;
+
+        impl CostFunction for Expanded {
+            type Cost = (usize, bool);
+
+            fn cost(&mut self, node: ExprRef, args: &[(usize, bool)]) -> (usize, bool) {
+                let mut size = args.iter().map(|(size, _)| size).sum::<usize>() + 1;
+                if node.is::<Times>() && args.iter().any(|(_, is_sum)| *is_sum) {
+                    size += 100;
+                }
+                (size, node.is::<Plus>())
+            }
+        }
+
+        let expanded = simplify_with(factored, &mut Expanded);
+        assert_eq!(&expanded, &canonicalize(&x * &y + &x * &z));
+    }
+
+    #[test]
+    fn like_terms() {
+        let x = sym("x");
+        let y = sym("y");
+
+        assert_eq!(&simplify(&x + &x), &canonicalize(2 * &x));
+        assert_eq!(&simplify(2 * &x + 3 * &x), &canonicalize(5 * &x));
+        assert_eq!(&simplify(&x - &x), &(ZERO.clone() as Expr));
+        assert_eq!(&simplify(&x * &y + 3 * &y * &x - 4 * &x * &y), &(ZERO.clone() as Expr));
+        assert_eq!(&simplify(&x * &x * 2 - x.clone().pow(2)), &canonicalize(x.clone().pow(2)));
+        assert_eq!(&collect_like_terms(sin(&x + &x + 1)), &canonicalize(sin(2 * &x + 1).into()));
+
+        let d = symbolic_eval(derivative(x.clone().pow(2) + 2 * x.clone().pow(2), x.clone()).into());
+        assert_eq!(LaTex::convert_to_string(simplify(d)), "6 * x");
+    }
+
+    #[test]
+    fn expansion() {
+        let x = sym("x");
+        let y = sym("y");
+        let a = sym("a");
+
+        assert_eq!(&expand((&x + 1) * (&x - 1)), &canonicalize(x.clone().pow(2) - 1));
+        // the factors only become equal once expanded, and merge into a power
+        assert_eq!(&expand((&x - &a).pow(2) * (&a - &x).pow(2) * &y), &expand((&x - &a).pow(4) * &y));
+        assert_eq!(
+            &expand((&x + &y).pow(3)),
+            &canonicalize(x.clone().pow(3) + 3 * x.clone().pow(2) * &y + 3 * &x * y.clone().pow(2) + y.clone().pow(3)),
+        );
+        assert_eq!(
+            &expand((&x + &y + 1).pow(2)),
+            &canonicalize(x.clone().pow(2) + y.clone().pow(2) + 2 * &x * &y + 2 * &x + 2 * &y + 1),
+        );
+        assert_eq!(&expand((&x * &y * 2).pow(2)), &canonicalize(4 * x.clone().pow(2) * y.clone().pow(2)));
+        assert_eq!(&expand((&x + 1).pow(-2)), &canonicalize((x.clone().pow(2) + 2 * &x + 1).pow(-1)));
+        assert_eq!(&expand(&x * (&x + 1) - x.clone().pow(2)), &(x.clone() as Expr));
+
+        // coefficients with 40 digits
+        let big = expand((&x + 1).pow(140));
+        assert_eq!(&expand(big.clone() - big), &(ZERO.clone() as Expr));
+
+        let shallow = ExpandOptions::default().deep(false);
+        let inner = sin((&x + 1) * &y);
+        assert_eq!(&expand_with(inner.clone().into(), &shallow), &canonicalize(inner.into()));
+        assert_eq!(&expand(sin((&x + 1) * &y).into()), &canonicalize(sin(&x * &y + &y).into()));
+
+        let wrt_x = ExpandOptions::default().wrt([x.clone()]);
+        assert_eq!(
+            &expand_with((&a + &y + &x) * (&x + 1), &wrt_x),
+            &canonicalize(x.clone().pow(2) + (&a + &y + 1) * &x + &a + &y),
+        );
+    }
+
+    #[test]
+    fn factorization() {
+        let x = sym("x");
+        let y = sym("y");
+        let p = |e: Expr| canonicalize(e);
+
+        assert_eq!(&factor(2 * x.clone().pow(3) - 2 * &x), &p(2 * &x * (&x - 1) * (&x + 1)));
+        assert_eq!(&factor(x.clone().pow(2) + 2 * &x + 1), &p((&x + 1).pow(2)));
+        assert_eq!(&factor(x.clone().pow(2) / 2 - Expr::from(1i32) / 2), &p((&x - 1) * (&x + 1) / 2));
+        assert_eq!(&factor(x.clone().pow(2) + 1), &p(x.clone().pow(2) + 1));
+
+        // needs recombining, since it splits modulo every prime
+        let swinnerton_dyer = x.clone().pow(4) - 10 * x.clone().pow(2) + 1;
+        assert_eq!(&factor(swinnerton_dyer.clone()), &p(swinnerton_dyer));
+
+        // a characteristic polynomial, (x - 1) * (x - 2)^2 * (x^2 + x + 1) expanded
+        let char_poly = expand((&x - 1) * (&x - 2).pow(2) * (x.clone().pow(2) + &x + 1));
+        let (coef, factors) = factor_list(char_poly);
+        assert!(coef.is_one());
+        let mut multiplicities: Vec<u32> = factors.iter().map(|(_, m)| *m).collect();
+        multiplicities.sort();
+        assert_eq!(multiplicities, [1, 1, 2]);
+        assert!(factors.iter().any(|(f, m)| *m == 2 && PartialEq::eq(f, &p(&x - 2))));
+
+        let high = expand((x.clone().pow(5) + 3 * &x - 7) * (x.clone().pow(4) - &x + 11) * (2 * &x + 3));
+        assert_eq!(factor_list(high).1.len(), 3);
+
+        assert_eq!(&factor(x.clone().pow(2) - y.clone().pow(2)), &p((&x - &y) * (&x + &y)));
+        assert_eq!(
+            &factor(expand((&x * &y + 1).pow(2) * (&x - 2 * &y) * &y)),
+            &p(&y * (&x - 2 * &y) * (&x * &y + 1).pow(2)),
+        );
+        assert_eq!(&factor((x.clone().pow(2) - 1) / (&x + 1)), &p(&x - 1));
+
+        // too large to map to one variable, and three factors in three
+        let (w, z) = (sym("w"), sym("z"));
+        let wide = (&w + &x + &y + z.clone().pow(10)) * (&w - &x);
+        assert_eq!(&factor(expand(wide.clone())), &p(wide));
+        let three = (x.clone().pow(3) + &y * &z + 1) * (&x * &y - z.clone().pow(2) + 3) * (&x + &y + &z);
+        assert_eq!(&factor(expand(three.clone())), &p(three));
+        let repeated = (&x * &y - &z).pow(2) * (x.clone().pow(2) + y.clone().pow(2) * &z + 2) * (&y + 1);
+        assert_eq!(&factor(expand(repeated.clone())), &p(repeated));
+
+        assert_eq!(&factor(sin(&x).pow(2) - 1), &p((sin(&x) - 1) * (sin(&x) + 1)));
+    }
+
+    #[test]
+    fn polynomials() {
+        let x = sym("x");
+        let y = sym("y");
+        let z = sym("z");
+        let gens: Vec<Expr> = vec![x.clone().into(), y.clone().into(), z.clone().into()];
+        let poly = |e: Expr| Poly::from_expr(&e, &gens).unwrap();
+
+        let sum = poly(&x + &y);
+        assert_eq!(sum.pow(2), poly(x.clone().pow(2) + 2 * &x * &y + y.clone().pow(2)));
+        assert_eq!(&sum * &sum - poly(&x * &y * 2), poly(x.clone().pow(2) + y.clone().pow(2)));
+        assert!((&sum - &sum).is_zero());
+        assert_eq!(poly(sum.pow(3).to_expr()), sum.pow(3));
+        assert_eq!(&Poly::from_expr(&(&x - 1).pow(2), &[]).unwrap().to_expr(), &expand((&x - 1).pow(2)));
+
+        // x^2 z against x y^2
+        let p = poly(x.clone().pow(2) * &z + &x * y.clone().pow(2));
+        let leading = |order| p.clone().with_order(order).leading_term().unwrap().0.to_vec();
+        assert_eq!(leading(MonomialOrder::Lex), [2, 0, 1]);
+        assert_eq!(leading(MonomialOrder::Grlex), [2, 0, 1]);
+        assert_eq!(leading(MonomialOrder::Grevlex), [1, 2, 0]);
+        let q = poly(x.clone().pow(2) + y.clone().pow(3));
+        assert_eq!(q.clone().with_order(MonomialOrder::Lex).leading_term().unwrap().0, [2, 0, 0]);
+        assert_eq!(q.with_order(MonomialOrder::Grlex).leading_term().unwrap().0, [0, 3, 0]);
+
+        let x_only: Vec<Expr> = vec![x.clone().into()];
+        assert!(matches!(Poly::from_expr(&(Expr::from(1i32) / &x), &x_only), Err(PolyError::NotPolynomial(_))));
+        assert!(Poly::from_expr(&sin(&x).into(), &x_only).is_err());
+        assert!(Poly::from_expr(&(&x + &y), &x_only).is_err());
+        assert!(Poly::from_expr(&(sin(&x) + 1), &[sin(&x).into()]).is_ok());
+    }
+
+    #[test]
+    fn rational_functions() {
+        let x = sym("x");
+        let y = sym("y");
+        let p = |e: Expr| canonicalize(e);
+        let xs: Vec<Expr> = vec![x.clone().into()];
+        let poly = |e: Expr| Poly::from_expr(&e, &xs).unwrap();
+
+        let (q, r) = poly(x.clone().pow(3) - 1).div_rem(&poly(&x - 1));
+        assert_eq!(q, poly(x.clone().pow(2) + &x + 1));
+        assert!(r.is_zero());
+        let (q, r) = poly(x.clone().pow(3) + 2).div_rem(&poly(x.clone().pow(2) + 1));
+        assert_eq!((q, r), (poly(x.clone().into()), poly(2 - &x)));
+
+        let xy: Vec<Expr> = vec![x.clone().into(), y.clone().into()];
+        let f = Poly::from_expr(&(x.clone().pow(2) * &y + &x * y.clone().pow(2) + y.clone().pow(2)), &xy).unwrap();
+        let (q, r) = f.div_rem(&Poly::from_expr(&(&x * &y - 1), &xy).unwrap());
+        assert_eq!(q, Poly::from_expr(&(&x + &y), &xy).unwrap());
+        assert_eq!(r, Poly::from_expr(&(&x + y.clone().pow(2) + &y), &xy).unwrap());
+
+        let a = expand((&x + 1).pow(2) * (&x - &y) * 6);
+        let b = expand((&x + 1) * (&x - &y).pow(3) * 4);
+        assert_eq!(&gcd(a, b), &expand((&x + 1) * (&x - &y)));
+        assert_eq!(&gcd(x.clone().pow(2) + 1, &x + 1), &Expr::from(1i32));
+
+        let (numer, denom) = fraction(Expr::from(1i32) / &x + Expr::from(1i32) / &y);
+        assert_eq!((&numer, &denom), (&p(&x + &y), &p(&x * &y)));
+
+        assert_eq!(&cancel((x.clone().pow(2) - 1) / (&x + 1)), &p(&x - 1));
+        assert_eq!(&cancel((2 * &x + 2) / (4 * &x + 4)), &p(Expr::from(1i32) / 2));
+        assert_eq!(
+            &cancel(expand((&x + &y).pow(2)) / (x.clone().pow(2) - y.clone().pow(2))),
+            &p((&x + &y) / (&x - &y)),
+        );
+        assert_eq!(
+            &cancel(Expr::from(1i32) / &x + Expr::from(1i32) / (&x * (&x + 1))),
+            &p((&x + 2) / (x.clone().pow(2) + &x)),
+        );
+
+        // the derivative of a transfer function, (s + 1)^2 / (s + 2)^3
+        let s = sym("s");
+        let h: Expr = (&s + 1).pow(2) / (&s + 2).pow(3);
+        let dh = symbolic_eval(derivative(h, s.clone()));
+        assert_eq!(&cancel(dh), &cancel((&s + 1) * (1 - &s) / (&s + 2).pow(4)));
+    }
+
+    #[test]
+    fn groebner_bases() {
+        let x = sym("x");
+        let y = sym("y");
+        let p = |e: Expr| canonicalize(e);
+        let xy: Vec<Expr> = vec![x.clone().into(), y.clone().into()];
+
+        // solving by elimination, where the last element only has y
+        let system = [x.clone().pow(2) + y.clone().pow(2) - 1, &x - &y];
+        for method in [Method::Buchberger, Method::F4] {
+            let basis = groebner_with(&system, &xy, MonomialOrder::Lex, method).unwrap();
+            let exprs = basis.exprs();
+            assert_eq!(exprs.len(), 2);
+            assert_eq!(&exprs[0], &p(&x - &y));
+            assert_eq!(&exprs[1], &p(y.clone().pow(2) - Expr::from(1i32) / 2));
+        }
+
+        let f = [x.clone().pow(3) - 2 * &x * &y, x.clone().pow(2) * &y - 2 * y.clone().pow(2) + &x];
+        let expected = [p(x.clone().pow(2)), p(&x * &y), p(y.clone().pow(2) - &x / 2)];
+        for method in [Method::Buchberger, Method::F4] {
+            let basis = groebner_with(&f, &xy, MonomialOrder::Grlex, method).unwrap();
+            assert_eq!(basis.exprs().len(), 3);
+            for (a, b) in basis.exprs().iter().zip(&expected) {
+                assert_eq!(a, b);
+            }
+        }
+
+        let z = sym("z");
+        let cyclic = [&x + &y + &z, &x * &y + &y * &z + &z * &x, &x * &y * &z - 1];
+        for method in [Method::Buchberger, Method::F4] {
+            let basis = groebner_with(&cyclic, &[], MonomialOrder::Lex, method).unwrap();
+            let expected = [p(&x + &y + &z), p(y.clone().pow(2) + &y * &z + z.clone().pow(2)), p(z.clone().pow(3) - 1)];
+            assert_eq!(basis.exprs().len(), 3);
+            for (a, b) in basis.exprs().iter().zip(&expected) {
+                assert_eq!(a, b);
+            }
+        }
+
+        let basis = groebner(&system, &[], MonomialOrder::Grevlex).unwrap();
+        assert!(basis.contains(&(x.clone().pow(2) - y.clone().pow(2))));
+        assert!(!basis.contains(&x.clone().into()));
+        assert!(groebner(&[&x - 1, &x - 2], &[], MonomialOrder::Lex).unwrap().is_trivial());
+
+        let circle = [x.clone().pow(2) + y.clone().pow(2) - 1];
+        assert_eq!(&reduce(&(x.clone().pow(2) + y.clone().pow(2) + &x), &circle), &p(&x + 1));
+        assert_eq!(&reduce(&(x.clone().pow(4) + 2 * x.clone().pow(2) * y.clone().pow(2) + y.clone().pow(4)), &circle), &Expr::from(1i32));
+    }
+
+    #[test]
+    fn resultants() {
+        let x = sym("x");
+        let y = sym("y");
+        let (a, b, c) = (sym("a"), sym("b"), sym("c"));
+        let p = |e: Expr| canonicalize(e);
+
+        for method in [ResultantMethod::Sylvester, ResultantMethod::Subresultant] {
+            // eliminating x from the circle and the diagonal
+            let res = resultant_with(x.clone().pow(2) + y.clone().pow(2) - 1, &x - &y, x.clone(), method);
+            assert_eq!(&res, &p(2 * y.clone().pow(2) - 1));
+            assert_eq!(&resultant_with(x.clone().pow(2) - 1, &x - 1, x.clone(), method), &Expr::from(0i32));
+
+            let f: Expr = x.clone().pow(3) - 2 * &x + 5;
+            let g: Expr = 3 * x.clone().pow(2) + &x * &y - 4;
+            assert_eq!(
+                &expand(resultant_with(f.clone(), g.clone(), x.clone(), method)),
+                &expand(resultant(f, g, x.clone())),
+            );
+        }
+        // Res(x^2 - a, x - b) = b^2 - a, and Res(x - b, x^2 - a) the same
+        assert_eq!(&resultant(&x - &b, x.clone().pow(2) - &a, x.clone()), &p(b.clone().pow(2) - &a));
+
+        let quadratic = &a * x.clone().pow(2) + &b * &x + &c;
+        assert_eq!(&discriminant(quadratic, x.clone()), &p(b.clone().pow(2) - 4 * &a * &c));
+        let cubic = x.clone().pow(3) + &a * &x + &b;
+        assert_eq!(&discriminant(cubic, x.clone()), &p(-4 * a.clone().pow(3) - 27 * b.clone().pow(2)));
+        assert_eq!(&discriminant(expand((&x - 1).pow(2) * (&x + 2)), x.clone()), &Expr::from(0i32));
+    }
+
+    /// Evaluates a number built from rationals, `i`, sums, products and
+    /// powers, as a complex `(re, im)`, taking principal powers.
+    fn eval_complex(expr: &Expr) -> (f64, f64) {
+        if let Some(n) = expr.downcast::<Number>() {
+            let r = n.to_rational().unwrap();
+            return (r.numer().to_f64().unwrap() / r.denom().to_f64().unwrap(), 0.0);
+        }
+        if expr.downcast::<Constant>() == Some(&Constant::I) {
+            return (0.0, 1.0);
+        }
+        if let Some(plus) = expr.downcast::<Plus>() {
+            return plus.terms().iter().map(eval_complex).fold((0.0, 0.0), |(a, b), (c, d)| (a + c, b + d));
+        }
+        if let Some(times) = expr.downcast::<Times>() {
+            return times.terms().iter().map(eval_complex).fold((1.0, 0.0), |(a, b), (c, d)| (a * c - b * d, a * d + b * c));
+        }
+        let pow = expr.downcast::<crate::functions::Pow>().unwrap();
+        let (re, im) = eval_complex(&pow.base().into_expr());
+        let e = eval_complex(&pow.exp().into_expr()).0;
+        let (r, theta) = (re.hypot(im).powf(e), im.atan2(re) * e);
+        (r * theta.cos(), r * theta.sin())
+    }
+
+    #[test]
+    fn polynomial_roots() {
+        let x = sym("x");
+        let p = |e: Expr| canonicalize(e);
+
+        let rs = roots(expand((2 * &x - 1) * (&x + 3).pow(2) * &x), x.clone()).unwrap();
+        let expected = [(p(Expr::from(-3i32)), 2), (p(Expr::from(0i32)), 1), (p(Expr::from(1i32) / 2), 1)];
+        assert_eq!(rs.len(), 3);
+        for ((root, m), (e, n)) in rs.iter().zip(&expected) {
+            assert_eq!((root, m), (e, n));
+        }
+
+        let rs = roots(x.clone().pow(2) - 12, x.clone()).unwrap();
+        let sqrt3 = Expr::from(3i32).pow(Expr::new(Number::rational(1, 2)));
+        assert_eq!(&rs[0].0, &p(-2 * sqrt3.clone()));
+        assert_eq!(&rs[1].0, &p(2 * sqrt3));
+        assert!(roots(&x + sym("y"), x.clone()).is_err());
+
+        // a constant term far too large to find the divisors of
+        let big: Expr = Expr::new(Number::Integer("100000000000000000000000000000000000039".parse().unwrap()));
+        let rs = roots(&x - &big, x.clone()).unwrap();
+        assert_eq!(rs.len(), 1);
+        assert_eq!((&rs[0].0, rs[0].1), (&big, 1));
+
+        // checks that every root of each polynomial is one numerically
+        let polys: Vec<Expr> = vec![
+            x.clone().pow(2) + &x + 1,
+            x.clone().pow(3) - 2,
+            x.clone().pow(3) - 3 * &x + 1,
+            x.clone().pow(3) + 3 * &x + 5,
+            x.clone().pow(4) - 2,
+            x.clone().pow(4) + &x + 1,
+            x.clone().pow(4) - 4 * x.clone().pow(3) + 2 * &x + 7,
+            expand((x.clone().pow(3) - &x - 1).pow(2) * (&x - 2)),
+        ];
+        for poly in polys {
+            let f = Poly::from_expr(&poly, &[x.clone().into()]).unwrap();
+            let rs = roots(poly, x.clone()).unwrap();
+            assert_eq!(rs.iter().map(|(_, m)| m).sum::<u32>(), f.degree(0).unwrap());
+            let points: Vec<(f64, f64)> = rs.iter().map(|(root, _)| eval_complex(root)).collect();
+            for (i, a) in points.iter().enumerate() {
+                assert!(points[..i].iter().all(|b| (a.0 - b.0).hypot(a.1 - b.1) > 1e-6));
+            }
+            for (root, _) in rs {
+                let z = eval_complex(&root);
+                let value = f.terms().iter().fold((0.0, 0.0), |acc, (e, c)| {
+                    let mut term = (eval_complex(&(Expr::new((*c).clone()) as Expr)).0, 0.0);
+                    for _ in 0..e[0] {
+                        term = (term.0 * z.0 - term.1 * z.1, term.0 * z.1 + term.1 * z.0);
+                    }
+                    (acc.0 + term.0, acc.1 + term.1)
+                });
+                assert!(value.0.hypot(value.1) < 1e-6, "{:?} is not a root", root);
+            }
+        }
+
+        let quintic = roots(x.clone().pow(5) - &x - 1, x.clone()).unwrap();
+        assert_eq!(quintic.len(), 5);
+        assert!(quintic.iter().all(|(root, m)| root.is::<RootOf>() && *m == 1));
+    }
+
+    #[test]
+    fn real_root_isolation() {
+        use crate::number::Sign;
+
+        let x = sym("x");
+        let (minus_inf, inf) = (Number::Infinity(Sign::Minus), Number::Infinity(Sign::Plus));
+        let count = |poly: Expr, a: &Number, b: &Number| count_real_roots(poly, x.clone(), a, b).unwrap();
+
+        assert_eq!(count(x.clone().pow(2) + 1, &minus_inf, &inf), 0);
+        assert_eq!(count(x.clone().pow(5) - &x - 1, &minus_inf, &inf), 1);
+        assert_eq!(count(expand((&x - 1).pow(2) * (&x + 2)), &minus_inf, &inf), 2);
+        assert_eq!(count(x.clone().pow(3) - 2 * &x, &Number::Integer(0.into()), &inf), 1);
+        assert_eq!(count(x.clone().pow(3) - 2 * &x, &minus_inf, &Number::Integer(0.into())), 2);
+
+        let poly: Expr = x.clone().pow(3) - 2 * &x;
+        let intervals = real_root_intervals(poly.clone(), x.clone()).unwrap();
+        assert_eq!(intervals.len(), 3);
+        for (k, &expected) in [-std::f64::consts::SQRT_2, 0.0, std::f64::consts::SQRT_2].iter().enumerate() {
+            let (a, b) = (intervals[k].0.to_rational().unwrap(), intervals[k].1.to_rational().unwrap());
+            let to_f64 = |r: &num::BigRational| r.numer().to_f64().unwrap() / r.denom().to_f64().unwrap();
+            assert!(to_f64(&a) <= expected && expected <= to_f64(&b));
+            assert!(k == 0 || intervals[k - 1].1.to_rational().unwrap() <= a);
+        }
+
+        let width = Number::rational(1, 1_000_000);
+        let (a, b) = refine_root_interval(poly.clone(), x.clone(), intervals[2].clone(), &width).unwrap();
+        let (a, b) = (a.to_rational().unwrap(), b.to_rational().unwrap());
+        assert!(&b - &a <= width.to_rational().unwrap());
+        let two = num::BigRational::from_integer(2.into());
+        assert!(&a * &a < two && two < &b * &b);
+
+        // the root at zero is hit exactly while bisecting
+        assert_eq!(intervals[1], (Number::Integer(0.into()), Number::Integer(0.into())));
+
+        // bad arguments are errors rather than panics
+        let refine = |interval: (Number, Number), width: &Number| refine_root_interval(poly.clone(), x.clone(), interval, width);
+        let whole = (Number::Integer((-2).into()), Number::Integer(2.into()));
+        assert!(matches!(refine((minus_inf.clone(), inf.clone()), &width), Err(RefineError::NotRational(_))));
+        assert!(matches!(refine(intervals[2].clone(), &Number::Integer(0.into())), Err(RefineError::Width(_))));
+        assert!(matches!(refine(whole, &width), Err(RefineError::RootCount(3))));
+        assert!(matches!(refine((Number::Integer(1.into()), Number::Integer(1.into())), &width), Err(RefineError::RootCount(0))));
+        assert!(refine(intervals[1].clone(), &width).is_ok());
+    }
+
+    #[test]
+    fn partial_fractions() {
+        let x = sym("x");
+        let y = sym("y");
+        let (a, b) = (sym("a"), sym("b"));
+        let p = |e: Expr| canonicalize(e);
+        let one = || Expr::from(1i32);
+
+        assert_eq!(&together(one() / &x + one() / x.clone().pow(2)), &p((&x + 1) / x.clone().pow(2)));
+        assert_eq!(&together(one() / &x + one() / &y), &p((&x + &y) / &x / &y));
+        assert_eq!(&together(&a / 2 + &b / 3), &p((3 * &a + 2 * &b) / 6));
+        assert_eq!(&together(&x + 1), &p(&x + 1));
+
+        assert_eq!(&apart(one() / (x.clone().pow(2) - 1), x.clone()).unwrap(), &p(one() / (&x - 1) / 2 - one() / (&x + 1) / 2));
+        assert_eq!(&apart(one() / ((&x + 1) * (&x + 2)), x.clone()).unwrap(), &p(one() / (&x + 1) - one() / (&x + 2)));
+        assert_eq!(&apart(one() / (x.clone().pow(2) * (&x + 1)), x.clone()).unwrap(), &p(one() / x.clone().pow(2) - one() / &x + one() / (&x + 1)));
+
+        // a polynomial part, a repeated factor and an irreducible quadratic
+        let exprs: Vec<Expr> = vec![
+            (x.clone().pow(4) + 1) / (x.clone().pow(2) - 1),
+            (&x + 2) / ((&x - 1) * (x.clone().pow(2) + 1)),
+            (x.clone().pow(3) + 2 * &x) / ((&x + 3).pow(3) * (x.clone().pow(2) + &x + 1).pow(2)),
+        ];
+        for expr in exprs {
+            let split = apart(expr.clone(), x.clone()).unwrap();
+            assert!(split.is::<Plus>());
+            assert_eq!(&cancel(&split - &expr), &Expr::from(0i32));
+        }
+
+        let quadratic = apart((&x + 2) / ((&x - 1) * (x.clone().pow(2) + 1)), x.clone()).unwrap();
+        assert_eq!(&quadratic, &p(Expr::from(3i32) / (&x - 1) / 2 + (-3 * &x / 2 - one() / 2) / (x.clone().pow(2) + 1)));
+
+        // coefficients in other symbols
+        let s = sym("s");
+        let split = apart(one() / ((&s + &a) * (&s + 1)), s.clone()).unwrap();
+        assert_eq!(&cancel(&split - one() / ((&a - 1) * (&s + 1)) + one() / ((&a - 1) * (&s + &a))), &Expr::from(0i32));
+        let exprs: Vec<Expr> = vec![
+            (&x + &b) / ((&x - &a).pow(2) * (x.clone().pow(2) + &b)),
+            (x.clone().pow(3) + &a) / ((2 * &x + &b) * (&x - 1)),
+        ];
+        for expr in exprs {
+            let split = apart(expr.clone(), x.clone()).unwrap();
+            assert!(split.is::<Plus>());
+            assert_eq!(&cancel(&split - &expr), &Expr::from(0i32));
+        }
+        assert!(apart(one() / (sin(&x) + 1), x.clone()).is_err());
+    }
+
+    #[test]
+    fn coefficients() {
+        let (x, y) = (sym("x"), sym("y"));
+        let (a, b, c) = (sym("a"), sym("b"), sym("c"));
+        let p = |e: Expr| canonicalize(e);
+
+        let expr: Expr = &a * x.clone().pow(2) + &b * &x + &c * x.clone().pow(2) + 1;
+        assert_eq!(&collect(expr.clone(), std::slice::from_ref(&x)), &p((&a + &c) * x.clone().pow(2) + &b * &x + 1));
+        assert_eq!(&coeff(expr.clone(), x.clone(), 2).unwrap(), &p(&a + &c));
+        assert_eq!(&coeff(expr.clone(), x.clone(), 1).unwrap(), &p(b.clone().into()));
+        assert_eq!(&coeff(expr.clone(), x.clone(), 0).unwrap(), &Expr::from(1i32));
+        assert_eq!(&coeff(expr.clone(), x.clone(), 3).unwrap(), &Expr::from(0i32));
+        assert_eq!(degree(expr.clone(), x.clone()).unwrap(), Some(2));
+        assert_eq!(&leading_term(expr, x.clone()).unwrap(), &p((&a + &c) * x.clone().pow(2)));
+
+        // products and powers are expanded in `x` first
+        let expr: Expr = (&x + &a).pow(2) * &y + sin(&a) * &x;
+        assert_eq!(&coeff(expr.clone(), x.clone(), 1).unwrap(), &p(2 * &a * &y + sin(&a)));
+        assert_eq!(&coeff(expr.clone(), x.clone(), 0).unwrap(), &p(a.clone().pow(2) * &y));
+        assert_eq!(degree(expr.clone(), y.clone()).unwrap(), Some(1));
+
+        // collect keeps anything in the coefficients, the others need a polynomial
+        let expr: Expr = (&x + &a).pow(2) * &y + sin(&x) * &x;
+        assert_eq!(&collect(expr.clone(), &[x.clone(), y.clone()]), &p(x.clone().pow(2) * &y + 2 * &a * &x * &y + a.clone().pow(2) * &y + sin(&x) * &x));
+        assert!(matches!(coeff(expr, x.clone(), 1), Err(PolyError::NotPolynomial(f)) if PartialEq::eq(&f, &p(sin(&x).into()))));
+        assert!(degree(Expr::from(1i32) / &x, x.clone()).is_err());
+        assert!(degree(&x + Expr::from(1i32) / &x, x.clone()).is_err());
+        assert!(leading_term(sin(&x) * x.clone().pow(2) + &x, x.clone()).is_err());
+        assert!(degree(Expr::new(Number::Infinity(crate::number::Sign::Plus)), x.clone()).is_err());
+        assert_eq!(degree(sin(&y) * &x, x.clone()).unwrap(), Some(1));
+
+        // terms that cancel don't count
+        assert_eq!(degree(x.clone().pow(3) + &x - x.clone().pow(3), x.clone()).unwrap(), Some(1));
+        assert_eq!(degree(&x - &x, x.clone()).unwrap(), None);
+        assert_eq!(&leading_term(&x - &x, x.clone()).unwrap(), &Expr::from(0i32));
+    }
+
+    #[test]
+    fn horner_form() {
+        let (x, y) = (sym("x"), sym("y"));
+        let (a, b, c, d) = (sym("a"), sym("b"), sym("c"), sym("d"));
+        let p = |e: Expr| canonicalize(e);
+
+        let cubic: Expr = &a * x.clone().pow(3) + &b * x.clone().pow(2) + &c * &x + &d;
+        assert_eq!(&horner(cubic, std::slice::from_ref(&x)).unwrap(), &p(&x * (&x * (&a * &x + &b) + &c) + &d));
+        assert_eq!(&horner(x.clone().pow(4) + x.clone().pow(2), std::slice::from_ref(&x)).unwrap(), &p(x.clone().pow(2) * (x.clone().pow(2) + 1)));
+        assert_eq!(&horner(expand((&x + 1).pow(3)), std::slice::from_ref(&x)).unwrap(), &p(&x * (&x * (&x + 3) + 3) + 1));
+
+        // x and y are in three terms each, then y is in more of the rest
+        let expr: Expr = x.clone().pow(2) * &y + &x * &y + &y + &x;
+        let nested = horner(expr.clone(), &[x.clone(), y.clone()]).unwrap();
+        assert_eq!(&nested, &p(&x * (&y * (&x + 1) + 1) + &y));
+        assert_eq!(&expand(nested), &expand(expr));
+
+        let expr = expand((&x + &y + 1).pow(4));
+        assert_eq!(&expand(horner(expr.clone(), &[x.clone(), y.clone()]).unwrap()), &expr);
+
+        // not a polynomial in x, so there is no Horner form to give
+        assert!(horner(sin(&x) * x.clone().pow(2) + &x, std::slice::from_ref(&x)).is_err());
+        assert!(horner(&x + Expr::from(1i32) / &y, &[x.clone(), y.clone()]).is_err());
+    }
 }
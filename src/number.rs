@@ -1,6 +1,6 @@
-use std::{fmt, ops::{self, ControlFlow}};
+use std::ops::{self, ControlFlow};
 
-use num::{BigInt, BigRational, One, Signed, Zero};
+use num::{BigInt, BigRational, One, Signed, ToPrimitive, Zero};
 
 use crate::{basic::Basic, expr::{Expr, ExprRef}, undefined::UNDEFINED, visitor::Visitor};
 
@@ -63,6 +63,79 @@ impl Number {
             Number::Infinity(sign) => *sign == Sign::Minus,
         }
     }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Integer(_) => true,
+            Number::Rational(r) => r.is_integer(),
+            Number::Infinity(_) => false,
+        }
+    }
+
+    /// The exact fraction `numer / denom`.
+    pub fn rational(numer: impl Into<BigInt>, denom: impl Into<BigInt>) -> Number {
+        Number::from_rational(BigRational::new(numer.into(), denom.into()))
+    }
+
+    /// Builds a `Number` from a rational, collapsing it to
+    /// an integer when the denominator is one.
+    pub fn from_rational(r: BigRational) -> Number {
+        if r.is_integer() {
+            Number::Integer(r.to_integer())
+        } else {
+            Number::Rational(r)
+        }
+    }
+
+    pub fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Number::Integer(i) => Some(BigRational::from_integer(i.clone())),
+            Number::Rational(r) => Some(r.clone()),
+            Number::Infinity(_) => None,
+        }
+    }
+
+    /// Raises `self` to `exp` when the result is exactly representable,
+    /// which is only the case for integer exponents.
+    ///
+    /// Returns `None` for non-integer exponents and for zero raised to a
+    /// negative power.
+    pub fn pow(&self, exp: &Number) -> Option<Number> {
+        let exp = match exp {
+            Number::Integer(i) => i.clone(),
+            Number::Rational(r) if r.is_integer() => r.to_integer(),
+            _ => return None,
+        };
+
+        match self {
+            Number::Infinity(sign) => {
+                if exp.is_zero() {
+                    Some(Number::Integer(BigInt::one()))
+                } else if exp.is_negative() {
+                    Some(Number::Integer(BigInt::zero()))
+                } else if *sign == Sign::Minus && (&exp % 2u32).is_zero() {
+                    Some(Number::Infinity(Sign::Plus))
+                } else {
+                    Some(Number::Infinity(*sign))
+                }
+            }
+            _ => {
+                let base = self.to_rational()?;
+                if base.is_zero() && exp.is_negative() {
+                    return None;
+                }
+
+                let magnitude = exp.magnitude().to_usize()?;
+                let raised = num::pow::pow(base, magnitude);
+
+                Some(Number::from_rational(if exp.is_negative() {
+                    raised.recip()
+                } else {
+                    raised
+                }))
+            }
+        }
+    }
 }
 
 impl Basic for Number {
@@ -111,12 +184,41 @@ impl ops::Add<&'_ Number> for &'_ Number {
             
             (Number::Integer(a), Number::Integer(b)) => Number::Integer(a + b),
             (Number::Integer(a), Number::Rational(b))
-            | (Number::Rational(b), Number::Integer(a)) => Number::Rational(b + a),
-            (Number::Rational(a), Number::Rational(b)) => Number::Rational(a + b)
+            | (Number::Rational(b), Number::Integer(a)) => Number::from_rational(b + a),
+            (Number::Rational(a), Number::Rational(b)) => Number::from_rational(a + b)
         }
     }
 }
 
+impl ops::Neg for &'_ Number {
+    type Output = Number;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Number::Integer(i) => Number::Integer(-i),
+            Number::Rational(r) => Number::Rational(-r),
+            Number::Infinity(Sign::Plus) => Number::Infinity(Sign::Minus),
+            Number::Infinity(Sign::Minus) => Number::Infinity(Sign::Plus),
+        }
+    }
+}
+
+impl ops::Neg for Number {
+    type Output = Number;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+impl ops::Sub<&'_ Number> for &'_ Number {
+    type Output = Number;
+
+    fn sub(self, rhs: &Number) -> Self::Output {
+        self + &-rhs
+    }
+}
+
 impl ops::Mul<&'_ Number> for Number {
     type Output = Number;
 
@@ -147,8 +249,8 @@ impl ops::Mul<&'_ Number> for &'_ Number {
 
             (Number::Integer(a), Number::Integer(b)) => Number::Integer(a * b),
             (Number::Integer(a), Number::Rational(b))
-            | (Number::Rational(b), Number::Integer(a)) => Number::Rational(b * a),
-            (Number::Rational(a), Number::Rational(b)) => Number::Rational(a * b)
+            | (Number::Rational(b), Number::Integer(a)) => Number::from_rational(b * a),
+            (Number::Rational(a), Number::Rational(b)) => Number::from_rational(a * b)
         }
     }
 }
@@ -217,7 +319,26 @@ impl From<i128> for Expr {
 impl From<f32> for Expr {
     fn from(f: f32) -> Self {
         BigRational::from_float(f)
-            .map(|r| Expr::new(Number::Rational(r)) as Expr)
+            .map(|r| Expr::new(Number::from_rational(r)) as Expr)
+            .unwrap_or_else(|| {
+                // the float was not finite
+                if f.is_infinite() {
+                    Expr::new(Number::Infinity(if f.is_sign_negative() {
+                        Sign::Minus
+                    } else {
+                        Sign::Plus
+                    }))
+                } else {
+                    UNDEFINED.clone()
+                }
+            })
+    }
+}
+
+impl From<f64> for Expr {
+    fn from(f: f64) -> Self {
+        BigRational::from_float(f)
+            .map(|r| Expr::new(Number::from_rational(r)) as Expr)
             .unwrap_or_else(|| {
                 // the float was not finite
                 if f.is_infinite() {
@@ -0,0 +1,380 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use string_cache::DefaultAtom;
+
+use crate::{Number, basic::Basic, canonicalize::canonicalize, expr::{Expr, ExprRef}, fold::{Transformer, bottom_up}, functions::{Plus, Pow, Times}, number::{ONE, ZERO}, symbol::{Symbol, depends_on}, visitor::Visitor};
+
+/// A condition on what a [`Wild`] is allowed to match.
+#[derive(Debug, Clone)]
+pub enum WildPredicate {
+    /// Only matches numbers.
+    Number,
+    /// Only matches integers.
+    Integer,
+    /// Only matches symbols.
+    Symbol,
+    /// Only matches expressions that don't depend on the symbol.
+    Independent(Expr<Symbol>),
+}
+
+impl WildPredicate {
+    fn holds(&self, expr: ExprRef) -> bool {
+        match self {
+            WildPredicate::Number => expr.is::<Number>(),
+            WildPredicate::Integer => expr.downcast::<Number>().is_some_and(Number::is_integer),
+            WildPredicate::Symbol => expr.is::<Symbol>(),
+            WildPredicate::Independent(sym) => !depends_on(expr, sym),
+        }
+    }
+
+    fn key(&self) -> (u8, Option<&Symbol>) {
+        match self {
+            WildPredicate::Number => (0, None),
+            WildPredicate::Integer => (1, None),
+            WildPredicate::Symbol => (2, None),
+            WildPredicate::Independent(sym) => (3, Some(&**sym)),
+        }
+    }
+}
+
+impl PartialEq for WildPredicate {
+    fn eq(&self, other: &WildPredicate) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for WildPredicate {}
+
+impl PartialOrd for WildPredicate {
+    fn partial_cmp(&self, other: &WildPredicate) -> Option<Ordering> {
+        Some(Ord::cmp(self, other))
+    }
+}
+
+impl Ord for WildPredicate {
+    fn cmp(&self, other: &WildPredicate) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// A placeholder in a pattern, which matches any expression that
+/// satisfies all of its predicates.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Wild {
+    name: DefaultAtom,
+    predicates: Vec<WildPredicate>,
+}
+
+impl Wild {
+    pub fn as_str(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn predicates(&self) -> &[WildPredicate] {
+        &self.predicates
+    }
+
+    fn accepts(&self, expr: ExprRef) -> bool {
+        self.predicates.iter().all(|p| p.holds(expr))
+    }
+
+    /// Whether this can only match a single term of a canonical sum or
+    /// product, since several of them never make a number or a symbol.
+    fn is_single(&self) -> bool {
+        self.predicates.iter().any(|p| !matches!(p, WildPredicate::Independent(_)))
+    }
+}
+
+impl Basic for Wild {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_wild(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        if let Some(other) = other.downcast::<Self>() {
+            self == other
+        } else {
+            false
+        }
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+}
+
+pub fn wild(name: &str) -> Expr<Wild> {
+    wild_where(name, [])
+}
+
+/// A wild that only matches expressions satisfying every one of `predicates`.
+pub fn wild_where(name: &str, predicates: impl IntoIterator<Item = WildPredicate>) -> Expr<Wild> {
+    Expr::new(Wild {
+        name: DefaultAtom::from(name),
+        predicates: predicates.into_iter().collect(),
+    })
+}
+
+/// The expressions that the wilds of a pattern matched.
+#[derive(Debug, Clone, Default)]
+pub struct Bindings {
+    bindings: Vec<(DefaultAtom, Expr)>,
+}
+
+impl Bindings {
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.bindings.iter().find(|(n, _)| n.as_ref() == name).map(|(_, e)| e)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Expr)> {
+        self.bindings.iter().map(|(n, e)| (n.as_ref(), e))
+    }
+
+    /// Replaces each bound wild in `expr` with what it matched.
+    pub fn instantiate(&self, expr: Expr) -> Expr {
+        struct Instantiate<'a>(&'a Bindings);
+
+        impl Transformer for Instantiate<'_> {
+            fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+                let bound = expr.downcast::<Wild>().and_then(|w| self.0.get(w.as_str()));
+                ControlFlow::Continue(bound.cloned().unwrap_or(expr))
+            }
+        }
+
+        match bottom_up(expr.rf(), &mut Instantiate(self)) {
+            ControlFlow::Continue(expr) => canonicalize(expr),
+            ControlFlow::Break(_) => unreachable!(),
+        }
+    }
+
+    /// Binds `w` to `expr`, unless it is already bound to something else.
+    fn bind(mut self, w: &Wild, expr: Expr) -> Option<Self> {
+        match self.get(w.as_str()) {
+            Some(bound) => PartialEq::eq(bound, &expr).then_some(self),
+            None if w.accepts(expr.rf()) => {
+                self.bindings.push((w.name.clone(), expr));
+                Some(self)
+            }
+            None => None,
+        }
+    }
+}
+
+/// Matches `expr` against `pattern`, returning what each wild matched.
+///
+/// Both are canonicalized first. `Plus` and `Times` are matched as
+/// unordered bags of terms, where a wild standing on its own takes up
+/// any leftover terms, or the identity when there are none. So `a*x + b`
+/// matches `3 + 5*x` with `a = 5` and `b = 3`, and `5*x` with `b = 0`.
+pub fn match_pattern(pattern: &Expr, expr: &Expr) -> Option<Bindings> {
+    match_where(pattern, expr, |_| true)
+}
+
+/// The first match of `expr` against `pattern`, as in [`match_pattern`],
+/// for which `accept` holds. Matches are tried lazily, best first, and
+/// the search stops at the first one that is accepted.
+pub fn match_where(pattern: &Expr, expr: &Expr, mut accept: impl FnMut(&Bindings) -> bool) -> Option<Bindings> {
+    let mut res = None;
+    search(pattern, expr, &mut |b| {
+        if accept(&b) {
+            res = Some(b);
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    res
+}
+
+/// Every way that `expr` matches `pattern`, best first.
+///
+/// There can be exponentially many of these when lone wilds share the
+/// terms of a large sum, so prefer [`match_pattern`] or [`match_where`]
+/// when one match is enough.
+pub fn match_all(pattern: &Expr, expr: &Expr) -> Vec<Bindings> {
+    let mut matches = vec![];
+    search(pattern, expr, &mut |b| {
+        matches.push(b);
+        ControlFlow::Continue(())
+    });
+    matches
+}
+
+/// Calls `found` with each match in turn, until it breaks.
+type Found<'a> = dyn FnMut(Bindings) -> ControlFlow<()> + 'a;
+
+fn search(pattern: &Expr, expr: &Expr, found: &mut Found) {
+    let pattern = canonicalize(pattern.clone());
+    let expr = canonicalize(expr.clone());
+    let _ = match_expr(pattern.rf(), expr.rf(), Bindings::default(), found);
+}
+
+fn match_expr(pattern: ExprRef, expr: ExprRef, b: Bindings, found: &mut Found) -> ControlFlow<()> {
+    if let Some(w) = pattern.downcast::<Wild>() {
+        return match b.bind(w, expr.into_expr()) {
+            Some(b) => found(b),
+            None => ControlFlow::Continue(()),
+        };
+    }
+
+    if let Some(plus) = pattern.downcast::<Plus>() {
+        let terms = match expr.downcast::<Plus>() {
+            Some(plus) => plus.terms().to_vec(),
+            None => vec![expr.into_expr()],
+        };
+        return match_bag(plus.terms(), terms, b, Bag::Plus, found);
+    }
+
+    if let Some(times) = pattern.downcast::<Times>() {
+        let factors = match expr.downcast::<Times>() {
+            Some(times) => times.terms().to_vec(),
+            None => vec![expr.into_expr()],
+        };
+        return match_bag(times.terms(), factors, b, Bag::Times, found);
+    }
+
+    if let Some(pow) = pattern.downcast::<Pow>() {
+        if let Some(expr) = expr.downcast::<Pow>() {
+            match_seq(&pow.args(), &expr.args(), b.clone(), found)?;
+        }
+
+        // `x` is also `x^1`
+        return match_seq(&pow.args(), &[expr.into_expr(), ONE.clone()], b, found);
+    }
+
+    let args = pattern.args();
+    if args.is_empty() {
+        return if PartialEq::eq(&*pattern, &*expr) { found(b) } else { ControlFlow::Continue(()) };
+    }
+
+    // Nodes match when they are the same kind of node, which is checked
+    // by rebuilding the pattern around the args of `expr`.
+    let expr_args = expr.args();
+    let same_head = expr_args.len() == args.len()
+        && pattern.with_args(expr_args.clone()).is_some_and(|rebuilt| PartialEq::eq(&*rebuilt, &*expr));
+    if !same_head {
+        return ControlFlow::Continue(());
+    }
+
+    match_seq(&args, &expr_args, b, found)
+}
+
+/// Matches each of `patterns` against the expression at the same position.
+fn match_seq(patterns: &[Expr], exprs: &[Expr], b: Bindings, found: &mut Found) -> ControlFlow<()> {
+    match (patterns.split_first(), exprs.split_first()) {
+        (Some((pattern, patterns)), Some((expr, exprs))) => {
+            match_expr(pattern.rf(), expr.rf(), b, &mut |b| match_seq(patterns, exprs, b, found))
+        }
+        _ => found(b),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Bag {
+    Plus,
+    Times,
+}
+
+impl Bag {
+    fn combine(self, terms: Vec<Expr>) -> Expr {
+        match (self, terms.len()) {
+            (Bag::Plus, 0) => ZERO.clone(),
+            (Bag::Times, 0) => ONE.clone(),
+            (_, 1) => terms.into_iter().next().unwrap(),
+            (Bag::Plus, _) => canonicalize(Expr::new(Plus::new(terms))),
+            (Bag::Times, _) => canonicalize(Expr::new(Times::new(terms))),
+        }
+    }
+}
+
+/// Matches the terms of a sum or product, in any order.
+fn match_bag(patterns: &[Expr], terms: Vec<Expr>, b: Bindings, bag: Bag, found: &mut Found) -> ControlFlow<()> {
+    let (wilds, fixed): (Vec<&Expr>, Vec<&Expr>) = patterns.iter().partition(|p| p.is::<Wild>());
+    let wilds: Vec<&Wild> = wilds.iter().map(|w| w.downcast::<Wild>().unwrap()).collect();
+    place(&fixed, terms, b, &wilds, bag, found)
+}
+
+/// Matches every pattern that isn't a lone wild against one of the
+/// terms, then shares whatever is left over between the lone wilds.
+fn place(fixed: &[&Expr], terms: Vec<Expr>, b: Bindings, wilds: &[&Wild], bag: Bag, found: &mut Found) -> ControlFlow<()> {
+    let Some((pattern, fixed)) = fixed.split_first() else {
+        return share(wilds, &terms, b, bag, found);
+    };
+
+    for (i, term) in terms.iter().enumerate() {
+        match_expr(pattern.rf(), term.rf(), b.clone(), &mut |b| {
+            let mut rest = terms.clone();
+            rest.remove(i);
+            place(fixed, rest, b, wilds, bag, found)
+        })?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// Splits `terms` between `wilds` in every way, trying the splits that
+/// leave the fewest wilds with nothing first.
+fn share(wilds: &[&Wild], terms: &[Expr], b: Bindings, bag: Bag, found: &mut Found) -> ControlFlow<()> {
+    if wilds.is_empty() {
+        return if terms.is_empty() { found(b) } else { ControlFlow::Continue(()) };
+    }
+
+    let most = wilds.len().min(terms.len());
+    let least = if terms.is_empty() { 0 } else { 1 };
+    for used in (least..=most).rev() {
+        subsets(wilds.len(), used, &mut vec![], &mut |used| {
+            let mut parts = vec![vec![]; used.len()];
+            split(terms, used, wilds, &mut parts, &mut |parts| {
+                let mut b = Some(b.clone());
+                for (i, w) in wilds.iter().enumerate() {
+                    let part = used.iter().position(|&u| u == i).map_or(vec![], |k| parts[k].clone());
+                    b = b.and_then(|b| b.bind(w, bag.combine(part)));
+                }
+                match b {
+                    Some(b) => found(b),
+                    None => ControlFlow::Continue(()),
+                }
+            })
+        })?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// Calls `f` with each set of `size` of the indices `0..n`, in order.
+fn subsets(n: usize, size: usize, chosen: &mut Vec<usize>, f: &mut dyn FnMut(&[usize]) -> ControlFlow<()>) -> ControlFlow<()> {
+    if chosen.len() == size {
+        return f(chosen);
+    }
+
+    let start = chosen.last().map_or(0, |&i| i + 1);
+    for i in start..n {
+        chosen.push(i);
+        subsets(n, size, chosen, f)?;
+        chosen.pop();
+    }
+    ControlFlow::Continue(())
+}
+
+/// Calls `f` with each way of putting `terms` into `parts`, one for
+/// each of the `used` wilds, so that none of them is left empty. A term
+/// only goes to a wild that would accept it on its own, since a sum or
+/// product of terms can't satisfy a predicate that one of them fails,
+/// and to a wild that only takes one term while it still has none.
+fn split(terms: &[Expr], used: &[usize], wilds: &[&Wild], parts: &mut Vec<Vec<Expr>>, f: &mut dyn FnMut(&[Vec<Expr>]) -> ControlFlow<()>) -> ControlFlow<()> {
+    let empty = parts.iter().filter(|part| part.is_empty()).count();
+    let Some((term, terms)) = terms.split_first() else {
+        return if empty == 0 { f(parts) } else { ControlFlow::Continue(()) };
+    };
+    if terms.len() + 1 < empty {
+        return ControlFlow::Continue(());
+    }
+
+    for (k, &i) in used.iter().enumerate() {
+        if !wilds[i].accepts(term.rf()) || (wilds[i].is_single() && !parts[k].is_empty()) {
+            continue;
+        }
+        parts[k].push(term.clone());
+        split(terms, used, wilds, parts, f)?;
+        parts[k].pop();
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,586 @@
+use std::{cmp::Ordering, collections::BTreeMap, error, fmt, ops};
+
+use num::{BigInt, BigRational, Integer, One, ToPrimitive, Zero};
+
+use crate::{Number, canonicalize::canonicalize, convert::{Convert, LaTex}, expand::expand, expr::Expr, functions::{Plus, Pow, Times}};
+
+/// How the monomials of a [`Poly`] are ordered, which decides its
+/// leading term.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonomialOrder {
+    /// By the exponent of the first generator, then the second, and so on.
+    #[default]
+    Lex,
+    /// By total degree, then as `Lex`.
+    Grlex,
+    /// By total degree, then the smaller exponent of the last generator
+    /// is the larger monomial, then of the one before it, and so on.
+    Grevlex,
+}
+
+impl MonomialOrder {
+    pub fn cmp(&self, a: &[u32], b: &[u32]) -> Ordering {
+        let degree = |e: &[u32]| e.iter().sum::<u32>();
+        match self {
+            MonomialOrder::Lex => a.cmp(b),
+            MonomialOrder::Grlex => degree(a).cmp(&degree(b)).then_with(|| a.cmp(b)),
+            MonomialOrder::Grevlex => degree(a).cmp(&degree(b)).then_with(|| b.iter().rev().cmp(a.iter().rev())),
+        }
+    }
+}
+
+/// Why an expression could not be turned into a [`Poly`].
+#[derive(Debug, Clone)]
+pub enum PolyError {
+    /// The expression has this factor, which is not a natural power of
+    /// a generator or a rational number.
+    NotPolynomial(Expr),
+}
+
+impl fmt::Display for PolyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolyError::NotPolynomial(factor) => {
+                write!(f, "not a polynomial in the generators: {}", LaTex::convert_to_string(factor.clone()))
+            }
+        }
+    }
+}
+
+impl error::Error for PolyError {}
+
+/// A sparse polynomial in a list of generators, which are usually
+/// symbols, with rational coefficients.
+///
+/// Each term is keyed by its exponent vector, with one exponent per
+/// generator. Arithmetic between polynomials needs them to have the same
+/// generators.
+#[derive(Debug, Clone)]
+pub struct Poly {
+    gens: Vec<Expr>,
+    order: MonomialOrder,
+    terms: BTreeMap<Vec<u32>, Number>,
+}
+
+impl Poly {
+    pub fn zero(gens: Vec<Expr>) -> Self {
+        Self {
+            gens,
+            order: MonomialOrder::default(),
+            terms: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a polynomial from `(exponents, coefficient)` pairs, adding
+    /// up the coefficients of repeated exponents.
+    pub fn from_terms(gens: Vec<Expr>, terms: impl IntoIterator<Item = (Vec<u32>, Number)>) -> Self {
+        let mut poly = Self::zero(gens);
+        for (exps, coef) in terms {
+            assert_eq!(exps.len(), poly.gens.len(), "one exponent per generator");
+            poly.add_term(exps, &coef);
+        }
+        poly
+    }
+
+    pub fn constant(gens: Vec<Expr>, c: Number) -> Self {
+        let n = gens.len();
+        Self::from_terms(gens, [(vec![0; n], c)])
+    }
+
+    /// The `i`th generator as a polynomial.
+    pub fn gen(gens: Vec<Expr>, i: usize) -> Self {
+        let mut exps = vec![0; gens.len()];
+        exps[i] = 1;
+        Self::from_terms(gens, [(exps, Number::Integer(BigInt::from(1)))])
+    }
+
+    pub fn with_order(mut self, order: MonomialOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn gens(&self) -> &[Expr] {
+        &self.gens
+    }
+
+    pub fn order(&self) -> MonomialOrder {
+        self.order
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The terms, largest first in the monomial order.
+    pub fn terms(&self) -> Vec<(&[u32], &Number)> {
+        let mut terms: Vec<(&[u32], &Number)> = self.terms.iter().map(|(e, c)| (&e[..], c)).collect();
+        terms.sort_by(|(a, _), (b, _)| self.order.cmp(b, a));
+        terms
+    }
+
+    /// The coefficient of the monomial with these exponents.
+    pub fn coeff(&self, exps: &[u32]) -> Number {
+        self.terms.get(exps).cloned().unwrap_or_else(|| Number::Integer(BigInt::zero()))
+    }
+
+    /// The largest term in the monomial order, or `None` for zero.
+    pub fn leading_term(&self) -> Option<(&[u32], &Number)> {
+        self.terms
+            .iter()
+            .max_by(|(a, _), (b, _)| self.order.cmp(a, b))
+            .map(|(e, c)| (&e[..], c))
+    }
+
+    pub fn leading_coeff(&self) -> Option<&Number> {
+        self.leading_term().map(|(_, c)| c)
+    }
+
+    /// The largest sum of exponents of any term, or `None` for zero.
+    pub fn total_degree(&self) -> Option<u32> {
+        self.terms.keys().map(|e| e.iter().sum()).max()
+    }
+
+    /// The largest exponent of the `i`th generator, or `None` for zero.
+    pub fn degree(&self, i: usize) -> Option<u32> {
+        self.terms.keys().map(|e| e[i]).max()
+    }
+
+    pub fn scale(&self, c: &Number) -> Poly {
+        let terms = self.terms.iter().map(|(e, coef)| (e.clone(), coef * c));
+        Self::from_terms(self.gens.clone(), terms).with_order(self.order)
+    }
+
+    /// `self` divided by its leading coefficient, or zero for zero.
+    pub fn monic(&self) -> Poly {
+        match self.leading_coeff() {
+            Some(lc) => self.scale(&div(&Number::Integer(BigInt::one()), lc)),
+            None => self.clone(),
+        }
+    }
+
+    pub fn pow(&self, mut n: u32) -> Poly {
+        let mut result = Self::constant(self.gens.clone(), Number::Integer(BigInt::from(1))).with_order(self.order);
+        let mut base = self.clone();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = &result * &base;
+            }
+            base = &base * &base;
+            n >>= 1;
+        }
+        result
+    }
+
+    /// Divides `self` by `divisor`, as `(quotient, remainder)` with
+    /// `self = quotient * divisor + remainder`, where no term of the
+    /// remainder is divisible by the leading term of `divisor`.
+    ///
+    /// In one generator this is the usual division with remainder. In
+    /// several it depends on the monomial order, except that the
+    /// remainder is always zero when `divisor` divides `self`.
+    ///
+    /// Panics when `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Poly) -> (Poly, Poly) {
+        self.assert_same_gens(divisor);
+        let (lead, lc) = divisor.leading_term().expect("division by the zero polynomial");
+        let (lead, lc) = (lead.to_vec(), lc.clone());
+
+        let mut quot = Poly::zero(self.gens.clone()).with_order(self.order);
+        let mut rem = quot.clone();
+        let mut p = self.clone();
+        while let Some((exps, coef)) = p.leading_term() {
+            let term = Poly::from_terms(self.gens.clone(), [(exps.to_vec(), coef.clone())]).with_order(self.order);
+            match monomial_div(exps, &lead) {
+                Some(exps) => {
+                    let factor = Poly::from_terms(self.gens.clone(), [(exps, div(coef, &lc))]).with_order(self.order);
+                    p = p - &factor * divisor;
+                    quot = quot + factor;
+                }
+                None => {
+                    p = p - &term;
+                    rem = rem + term;
+                }
+            }
+        }
+        (quot, rem)
+    }
+
+    /// `self / divisor`, if `divisor` divides `self`.
+    pub fn div_exact(&self, divisor: &Poly) -> Option<Poly> {
+        let (quot, rem) = self.div_rem(divisor);
+        rem.is_zero().then_some(quot)
+    }
+
+    /// Splits `self` into a number and a polynomial with integer
+    /// coefficients that have no common divisor, and a positive leading
+    /// coefficient. The number is zero for zero.
+    pub fn primitive(&self) -> (Number, Poly) {
+        let Some(lc) = self.leading_coeff() else {
+            return (Number::Integer(BigInt::zero()), self.clone());
+        };
+
+        let coefs: Vec<BigRational> = self.terms.values().map(|c| c.to_rational().unwrap()).collect();
+        let denom = coefs.iter().fold(BigInt::one(), |l, c| l.lcm(c.denom()));
+        let mut numer = coefs.iter().fold(BigInt::zero(), |g, c| g.gcd(&(c * &denom).to_integer()));
+        if lc.is_negative() {
+            numer = -numer;
+        }
+
+        let content = Number::from_rational(BigRational::new(numer, denom));
+        let primitive = self.scale(&div(&Number::Integer(BigInt::one()), &content));
+        (content, primitive)
+    }
+
+    /// The greatest common divisor of `self` and `other`, as a primitive
+    /// polynomial in the sense of [`Poly::primitive`], so any common
+    /// numeric factor is left out.
+    ///
+    /// The gcd is found one generator at a time, as the gcd of the
+    /// contents times the gcd of the primitive parts, by the subresultant
+    /// PRS.
+    pub fn gcd(&self, other: &Poly) -> Poly {
+        self.assert_same_gens(other);
+        if self.is_zero() {
+            return other.primitive().1;
+        }
+        if other.is_zero() {
+            return self.primitive().1;
+        }
+
+        let one = Poly::constant(self.gens.clone(), Number::Integer(BigInt::one())).with_order(self.order);
+        let Some(v) = (0..self.gens.len()).find(|&v| self.degree(v) > Some(0) || other.degree(v) > Some(0)) else {
+            return one;
+        };
+
+        let (ca, a) = self.content_in(v);
+        let (cb, b) = other.content_in(v);
+        let (mut a, mut b) = if a.degree(v) >= b.degree(v) { (a, b) } else { (b, a) };
+
+        let (mut g, mut h) = (one.clone(), one.clone());
+        loop {
+            let delta = a.degree(v).unwrap() - b.degree(v).unwrap();
+            let r = a.prem(&b, v);
+            if r.is_zero() {
+                break;
+            }
+            if r.degree(v) == Some(0) {
+                b = one;
+                break;
+            }
+
+            a = b;
+            b = r.div_exact(&(&g * &h.pow(delta))).unwrap();
+            g = a.coeff_in(v, a.degree(v).unwrap());
+            h = if delta == 0 { h } else { g.pow(delta).div_exact(&h.pow(delta - 1)).unwrap() };
+        }
+
+        (ca.gcd(&cb) * b.content_in(v).1).primitive().1
+    }
+
+    /// `(s, t)` with `s * self + t * other = 1`, for coprime `self` and
+    /// `other` in one generator, by the extended Euclidean algorithm.
+    pub fn bezout(&self, other: &Poly) -> (Poly, Poly) {
+        self.assert_same_gens(other);
+        let one = Poly::constant(self.gens.clone(), Number::Integer(BigInt::one())).with_order(self.order);
+        let zero = Poly::zero(self.gens.clone()).with_order(self.order);
+        let (mut r0, mut r1) = (self.clone(), other.clone());
+        let (mut s0, mut s1) = (one.clone(), zero.clone());
+        let (mut t0, mut t1) = (zero, one);
+        while !r1.is_zero() {
+            let (q, r) = r0.div_rem(&r1);
+            r0 = std::mem::replace(&mut r1, r);
+            let s = &s0 - &(&q * &s1);
+            s0 = std::mem::replace(&mut s1, s);
+            let t = &t0 - &(&q * &t1);
+            t0 = std::mem::replace(&mut t1, t);
+        }
+
+        // r0 is a nonzero constant
+        let inv = div(&Number::Integer(BigInt::one()), r0.leading_coeff().unwrap());
+        (s0.scale(&inv), t0.scale(&inv))
+    }
+
+    /// The partial derivative with respect to the `v`th generator.
+    pub fn derivative(&self, v: usize) -> Poly {
+        let terms = self.terms.iter().filter(|(e, _)| e[v] > 0).map(|(e, c)| {
+            let coef = c * &Number::Integer(BigInt::from(e[v]));
+            let mut e = e.clone();
+            e[v] -= 1;
+            (e, coef)
+        });
+        Poly::from_terms(self.gens.clone(), terms).with_order(self.order)
+    }
+
+    /// The coefficient of the `v`th generator to the `k`, as a
+    /// polynomial in the other generators.
+    pub fn coeff_in(&self, v: usize, k: u32) -> Poly {
+        let terms = self.terms.iter().filter(|(e, _)| e[v] == k).map(|(e, c)| {
+            let mut e = e.clone();
+            e[v] = 0;
+            (e, c.clone())
+        });
+        Poly::from_terms(self.gens.clone(), terms).with_order(self.order)
+    }
+
+    /// Splits `self` into the gcd of its coefficients as a polynomial in
+    /// `v` and what is left.
+    fn content_in(&self, v: usize) -> (Poly, Poly) {
+        let mut powers: Vec<u32> = self.terms.keys().map(|e| e[v]).collect();
+        powers.sort();
+        powers.dedup();
+        let content = powers
+            .into_iter()
+            .fold(Poly::zero(self.gens.clone()).with_order(self.order), |g, k| g.gcd(&self.coeff_in(v, k)));
+        let rest = self.div_exact(&content).unwrap();
+        (content, rest)
+    }
+
+    /// The pseudo-remainder of `self` by `b` as polynomials in `v`,
+    /// `lc(b)^(deg self - deg b + 1) * self mod b`.
+    ///
+    /// Panics when `b` is zero.
+    pub fn prem(&self, b: &Poly, v: usize) -> Poly {
+        let db = b.degree(v).unwrap();
+        let lc = b.coeff_in(v, db);
+        let mut steps = self.degree(v).map_or(0, |d| d + 1).saturating_sub(db);
+        let mut r = self.clone();
+        while let Some(dr) = r.degree(v).filter(|&dr| dr >= db) {
+            let mut exps = vec![0; self.gens.len()];
+            exps[v] = dr - db;
+            let shift = Poly::from_terms(self.gens.clone(), [(exps, Number::Integer(BigInt::one()))]).with_order(self.order);
+            r = &r * &lc - r.coeff_in(v, dr) * shift * b;
+            steps -= 1;
+        }
+        r * lc.pow(steps)
+    }
+
+    fn add_term(&mut self, exps: Vec<u32>, coef: &Number) {
+        let sum = match self.terms.get(&exps) {
+            Some(old) => old + coef,
+            None => coef.clone(),
+        };
+
+        if sum.is_zero() {
+            self.terms.remove(&exps);
+        } else {
+            self.terms.insert(exps, sum);
+        }
+    }
+
+    fn assert_same_gens(&self, other: &Poly) {
+        let same = self.gens.len() == other.gens.len()
+            && self.gens.iter().zip(&other.gens).all(|(a, b)| PartialEq::eq(a, b));
+        assert!(same, "polynomials over different generators");
+    }
+
+    /// Reads `expr` as a polynomial in `gens`, after expanding it. With
+    /// no `gens`, every factor that isn't a number or a natural power is
+    /// taken to be a generator, as given by [`generators`].
+    ///
+    /// Fails when a term has a factor that is neither a rational number
+    /// nor a natural power of one of `gens`, like `1/x` or `sin(x)` in `x`.
+    pub fn from_expr(expr: &Expr, gens: &[Expr]) -> Result<Poly, PolyError> {
+        let mut polys = Self::from_exprs(std::slice::from_ref(expr), gens)?;
+        Ok(polys.remove(0))
+    }
+
+    /// Reads each of `exprs` as a polynomial like [`Poly::from_expr`],
+    /// all in the same generators. With no `gens`, these are the
+    /// generators of all of `exprs` together.
+    pub fn from_exprs(exprs: &[Expr], gens: &[Expr]) -> Result<Vec<Poly>, PolyError> {
+        let exprs: Vec<Expr> = exprs.iter().cloned().map(expand).collect();
+        let gens: Vec<Expr> = if gens.is_empty() {
+            let mut gens: Vec<Expr> = exprs.iter().flat_map(generators_of).collect();
+            gens.sort();
+            gens.dedup_by(|a, b| PartialEq::eq(&*a, &*b));
+            gens
+        } else {
+            gens.iter().cloned().map(canonicalize).collect()
+        };
+
+        exprs.iter().map(|expr| Self::from_expanded(expr, gens.clone())).collect()
+    }
+
+    fn from_expanded(expr: &Expr, gens: Vec<Expr>) -> Result<Poly, PolyError> {
+        let mut poly = Self::zero(gens);
+        for term in terms_of(expr) {
+            let mut exps = vec![0; poly.gens.len()];
+            let mut coef = Number::Integer(BigInt::from(1));
+            for factor in factors_of(&term) {
+                if let Some(n) = factor.downcast::<Number>().filter(|n| n.to_rational().is_some()) {
+                    coef = coef * n;
+                    continue;
+                }
+
+                let (base, n) = as_power(&factor);
+                match poly.gens.iter().position(|g| PartialEq::eq(g, &base)) {
+                    Some(i) => exps[i] += n,
+                    None => return Err(PolyError::NotPolynomial(factor)),
+                }
+            }
+            poly.add_term(exps, &coef);
+        }
+
+        Ok(poly)
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        let terms: Vec<Expr> = self
+            .terms
+            .iter()
+            .map(|(exps, c)| {
+                let mut factors = vec![Expr::new(c.clone()) as Expr];
+                for (g, &e) in self.gens.iter().zip(exps) {
+                    if e > 0 {
+                        factors.push(g.clone().pow(e));
+                    }
+                }
+                Expr::new(Times::new(factors)) as Expr
+            })
+            .collect();
+        canonicalize(Expr::new(Plus::new(terms)))
+    }
+}
+
+/// The generators that [`Poly::from_expr`] picks for `expr` when it is
+/// given none: every factor of the expanded `expr` that isn't a number,
+/// with natural powers counted as their base, in sorted order.
+pub fn generators(expr: &Expr) -> Vec<Expr> {
+    generators_of(&expand(expr.clone()))
+}
+
+fn generators_of(expr: &Expr) -> Vec<Expr> {
+    let mut gens: Vec<Expr> = terms_of(expr)
+        .iter()
+        .flat_map(factors_of)
+        .filter(|factor| !factor.is::<Number>())
+        .map(|factor| as_power(&factor).0)
+        .collect();
+    gens.sort();
+    gens.dedup_by(|a, b| PartialEq::eq(&*a, &*b));
+    gens
+}
+
+fn terms_of(expr: &Expr) -> Vec<Expr> {
+    match expr.downcast::<Plus>() {
+        Some(plus) => plus.terms().to_vec(),
+        None => vec![expr.clone()],
+    }
+}
+
+fn factors_of(term: &Expr) -> Vec<Expr> {
+    match term.downcast::<Times>() {
+        Some(times) => times.terms().to_vec(),
+        None => vec![term.clone()],
+    }
+}
+
+/// Splits `x^n`, for a natural `n`, into `(x, n)`, and anything else
+/// into `(factor, 1)`.
+pub(crate) fn as_power(factor: &Expr) -> (Expr, u32) {
+    if let Some(pow) = factor.downcast::<Pow>() {
+        if let Some(Number::Integer(n)) = pow.exp().downcast::<Number>() {
+            if let Some(n) = n.to_u32().filter(|&n| n > 0) {
+                return (pow.base().into_expr(), n);
+            }
+        }
+    }
+    (factor.clone(), 1)
+}
+
+/// `a / b` as monomials, if `b` divides `a`.
+fn monomial_div(a: &[u32], b: &[u32]) -> Option<Vec<u32>> {
+    a.iter().zip(b).map(|(a, b)| a.checked_sub(*b)).collect()
+}
+
+fn div(a: &Number, b: &Number) -> Number {
+    a * b.pow(&Number::Integer(BigInt::from(-1))).unwrap()
+}
+
+fn monomial_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    a.iter().zip(b).map(|(a, b)| a + b).collect()
+}
+
+impl PartialEq for Poly {
+    fn eq(&self, other: &Poly) -> bool {
+        self.gens.len() == other.gens.len()
+            && self.gens.iter().zip(&other.gens).all(|(a, b)| PartialEq::eq(a, b))
+            && self.terms == other.terms
+    }
+}
+
+impl Eq for Poly {}
+
+impl ops::Add<&'_ Poly> for &'_ Poly {
+    type Output = Poly;
+
+    fn add(self, rhs: &Poly) -> Self::Output {
+        self.assert_same_gens(rhs);
+        let mut res = self.clone();
+        for (e, c) in &rhs.terms {
+            res.add_term(e.clone(), c);
+        }
+        res
+    }
+}
+
+impl ops::Sub<&'_ Poly> for &'_ Poly {
+    type Output = Poly;
+
+    fn sub(self, rhs: &Poly) -> Self::Output {
+        self + &-rhs
+    }
+}
+
+impl ops::Mul<&'_ Poly> for &'_ Poly {
+    type Output = Poly;
+
+    fn mul(self, rhs: &Poly) -> Self::Output {
+        self.assert_same_gens(rhs);
+        let mut res = Poly::zero(self.gens.clone()).with_order(self.order);
+        for (ea, ca) in &self.terms {
+            for (eb, cb) in &rhs.terms {
+                res.add_term(monomial_mul(ea, eb), &(ca * cb));
+            }
+        }
+        res
+    }
+}
+
+impl ops::Neg for &'_ Poly {
+    type Output = Poly;
+
+    fn neg(self) -> Self::Output {
+        self.scale(&Number::Integer(BigInt::from(-1)))
+    }
+}
+
+macro_rules! impl_owned_ops {
+    ($($trait:ident $method:ident),*) => {$(
+        impl ops::$trait<Poly> for Poly {
+            type Output = Poly;
+
+            fn $method(self, rhs: Poly) -> Self::Output {
+                ops::$trait::$method(&self, &rhs)
+            }
+        }
+
+        impl ops::$trait<&'_ Poly> for Poly {
+            type Output = Poly;
+
+            fn $method(self, rhs: &Poly) -> Self::Output {
+                ops::$trait::$method(&self, rhs)
+            }
+        }
+    )*};
+}
+
+impl_owned_ops!(Add add, Sub sub, Mul mul);
+
+impl ops::Neg for Poly {
+    type Output = Poly;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
@@ -0,0 +1,307 @@
+use std::ops::ControlFlow;
+
+use num::{BigInt, Integer, One};
+
+use crate::{Number, canonicalize::canonicalize, expr::Expr, factor::factor_list, fold::{Transformer, bottom_up}, functions::{Plus, Pow, Times}, polys::{Poly, PolyError, generators}, symbol::{Symbol, depends_on}};
+
+/// Splits `expr` into a numerator and a denominator, putting the terms
+/// of a sum over a common denominator, so `1/x + 1/y` gives
+/// `(y + x, x * y)`.
+///
+/// Nothing is cancelled, see [`cancel`] for that.
+pub fn fraction(expr: Expr) -> (Expr, Expr) {
+    let expr = canonicalize(expr);
+
+    if let Some(Number::Rational(r)) = expr.downcast::<Number>() {
+        return (Expr::new(Number::Integer(r.numer().clone())), Expr::new(Number::Integer(r.denom().clone())));
+    }
+
+    if let Some(times) = expr.downcast::<Times>() {
+        let (numers, denoms): (Vec<Expr>, Vec<Expr>) = times.terms().iter().cloned().map(fraction).unzip();
+        return (canonicalize(Expr::new(Times::new(numers))), canonicalize(Expr::new(Times::new(denoms))));
+    }
+
+    if let Some(plus) = expr.downcast::<Plus>() {
+        let mut terms = plus.terms().iter().cloned().map(fraction);
+        let first = terms.next().unwrap();
+        return terms.fold(first, |(a, b), (c, d)| {
+            if PartialEq::eq(&b, &d) {
+                (canonicalize(a + c), b)
+            } else {
+                (canonicalize(a * &d + c * &b), canonicalize(b * d))
+            }
+        });
+    }
+
+    if let Some(pow) = expr.downcast::<Pow>() {
+        match pow.exp().downcast::<Number>() {
+            Some(n @ Number::Integer(_)) => {
+                let (numer, denom) = fraction(pow.base().into_expr());
+                let k = Expr::new(if n.is_negative() { -n } else { n.clone() });
+                let (numer, denom) = (canonicalize(numer.pow(k.clone())), canonicalize(denom.pow(k)));
+                return if n.is_negative() { (denom, numer) } else { (numer, denom) };
+            }
+            Some(n) if n.is_negative() => {
+                return (Expr::from(1i32), canonicalize(pow.base().into_expr().pow(Expr::new(-n))));
+            }
+            _ => {}
+        }
+    }
+
+    (expr, Expr::from(1i32))
+}
+
+/// The greatest common divisor of the polynomials `a` and `b`, with
+/// integer coefficients and no numeric factor, or one when either isn't
+/// a polynomial. See [`Poly::gcd`].
+pub fn gcd(a: Expr, b: Expr) -> Expr {
+    match Poly::from_exprs(&[a, b], &[]) {
+        Ok(polys) => polys[0].gcd(&polys[1]).to_expr(),
+        Err(_) => Expr::from(1i32),
+    }
+}
+
+/// Reduces the rational function `expr` to lowest terms, as an expanded
+/// numerator over an expanded denominator with no common factor, so
+/// `(x^2 - 1) / (x^2 + x)` becomes `(x - 1) / x`.
+///
+/// Anything that isn't a sum, product or integer power, such as `sin(x)`,
+/// is treated as one more variable.
+pub fn cancel(expr: Expr) -> Expr {
+    let (numer, denom) = fraction(expr);
+    let Ok(polys) = Poly::from_exprs(&[numer.clone(), denom.clone()], &[]) else {
+        return canonicalize(numer / denom);
+    };
+    let (numer, denom) = (&polys[0], &polys[1]);
+    if numer.is_zero() {
+        return Expr::from(0i32);
+    }
+
+    let gcd = numer.gcd(denom);
+    let (cn, numer) = numer.div_exact(&gcd).unwrap().primitive();
+    let (cd, denom) = denom.div_exact(&gcd).unwrap().primitive();
+
+    let coef = cn * &cd.pow(&Number::Integer(-BigInt::one())).unwrap();
+    canonicalize(Expr::new(Times::new([
+        Expr::new(coef) as Expr,
+        numer.to_expr(),
+        denom.to_expr().pow(-1),
+    ])))
+}
+
+/// Puts each sum in `expr` over a common denominator, the product of
+/// the denominators of its terms raised to their largest powers, so
+/// `1/x + 1/x^2` becomes `(x + 1) / x^2`.
+///
+/// Denominators are compared as they are, without factoring them, and
+/// nothing is expanded or cancelled.
+pub fn together(expr: Expr) -> Expr {
+    match bottom_up(canonicalize(expr).rf(), &mut Together) {
+        ControlFlow::Continue(expr) => canonicalize(expr),
+        ControlFlow::Break(()) => unreachable!(),
+    }
+}
+
+struct Together;
+
+impl Transformer for Together {
+    fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+        let expr = canonicalize(expr);
+        let Some(plus) = expr.downcast::<Plus>() else {
+            return ControlFlow::Continue(expr);
+        };
+
+        let terms: Vec<_> = plus.terms().iter().map(split_denominator).collect();
+        let mut lcm = BigInt::one();
+        let mut denom: Vec<(Expr, BigInt)> = vec![];
+        for (_, n, factors) in &terms {
+            lcm = lcm.lcm(n);
+            for (base, k) in factors {
+                match denom.iter_mut().find(|(other, _)| PartialEq::eq(other, base)) {
+                    Some((_, max)) => *max = (&*max).max(k).clone(),
+                    None => denom.push((base.clone(), k.clone())),
+                }
+            }
+        }
+        if lcm.is_one() && denom.is_empty() {
+            return ControlFlow::Continue(expr);
+        }
+
+        // each numerator times the part of the common denominator that
+        // its own is missing
+        let numers: Vec<Expr> = terms
+            .into_iter()
+            .map(|(mut numer, n, factors)| {
+                numer.push(Expr::new(Number::Integer(&lcm / n)));
+                for (base, max) in &denom {
+                    let own = factors.iter().find(|(other, _)| PartialEq::eq(other, base)).map_or(BigInt::from(0), |(_, k)| k.clone());
+                    numer.push(base.clone().pow(Expr::new(Number::Integer(max - own))));
+                }
+                Expr::new(Times::new(numer)) as Expr
+            })
+            .collect();
+
+        let mut factors = vec![Expr::new(Plus::new(numers)) as Expr, Expr::new(Number::rational(1, lcm))];
+        factors.extend(denom.into_iter().map(|(base, max)| base.pow(Expr::new(Number::Integer(-max)))));
+        ControlFlow::Continue(canonicalize(Expr::new(Times::new(factors))))
+    }
+}
+
+/// Splits a term into the factors of its numerator, the denominator of
+/// its numeric coefficient and the bases of its negative integer powers
+/// with their exponents negated.
+fn split_denominator(term: &Expr) -> (Vec<Expr>, BigInt, Vec<(Expr, BigInt)>) {
+    let factors = match term.downcast::<Times>() {
+        Some(times) => times.terms().to_vec(),
+        None => vec![term.clone()],
+    };
+
+    let (mut numer, mut n, mut denom) = (vec![], BigInt::one(), vec![]);
+    for factor in factors {
+        if let Some(Number::Rational(r)) = factor.downcast::<Number>() {
+            numer.push(Expr::new(Number::Integer(r.numer().clone())) as Expr);
+            n = r.denom().clone();
+            continue;
+        }
+        if let Some(pow) = factor.downcast::<Pow>() {
+            if let Some(Number::Integer(k)) = pow.exp().downcast::<Number>().filter(|k| k.is_negative()) {
+                denom.push((pow.base().into_expr(), -k));
+                continue;
+            }
+        }
+        numer.push(factor);
+    }
+    (numer, n, denom)
+}
+
+/// Splits the rational function `expr` of `x` into a polynomial and a
+/// sum of proper fractions whose denominators are powers of the
+/// irreducible factors of its denominator, so `1 / (x^2 - 1)` becomes
+/// `1 / (2 * (x - 1)) - 1 / (2 * (x + 1))`.
+///
+/// Repeated factors give one fraction for each power up to their
+/// multiplicity, and each numerator has a lower degree than its factor,
+/// so a quadratic factor has a linear numerator.
+///
+/// Other symbols are coefficients, so the factors are irreducible over
+/// rational functions in them, and `1 / ((x + a) * (x + 1))` becomes
+/// `1 / ((a - 1) * (x + 1)) - 1 / ((a - 1) * (x + a))`. Fails when the
+/// numerator or denominator of `expr` isn't a polynomial in `x`.
+pub fn apart(expr: Expr, x: Expr<Symbol>) -> Result<Expr, PolyError> {
+    let (numer, denom) = fraction(cancel(expr));
+    let mut gens: Vec<Expr> = vec![x.clone().into()];
+    for g in generators(&numer).into_iter().chain(generators(&denom)) {
+        if gens.iter().any(|other| PartialEq::eq(other, &g)) {
+            continue;
+        }
+        if depends_on(g.rf(), &x) {
+            return Err(PolyError::NotPolynomial(g));
+        }
+        gens.push(g);
+    }
+    let numer = Poly::from_expr(&numer, &gens)?;
+    let denom = Poly::from_expr(&denom, &gens)?;
+
+    // numer / denom = quot / scale + rem / (scale * denom), where scale
+    // is free of x and gathers the denominators of the coefficients
+    let (quot, mut rem, mut scale) = pseudo_div(&numer, &denom);
+    let mut terms = vec![over(&quot, &scale)];
+
+    // denom = coef * f_1^m_1 * ... * f_n^m_n, times factors free of x
+    let (coef, factors) = factor_list(denom.to_expr());
+    scale = scale.scale(&coef);
+    let mut powers = vec![];
+    for (f, m) in factors {
+        let f = Poly::from_expr(&f, &gens)?;
+        if f.degree(0) == Some(0) {
+            scale = scale * f.pow(m);
+        } else {
+            powers.push((f, m));
+        }
+    }
+
+    let one = Poly::constant(gens.clone(), Number::Integer(BigInt::one()));
+    for (i, (f, m)) in powers.iter().enumerate() {
+        let power = f.pow(*m);
+        let rest = powers[i + 1..].iter().fold(one.clone(), |acc, (g, k)| acc * g.pow(*k));
+
+        // rem / (f^m * rest) = rem * s / (g * f^m) + rem * t / (g * rest),
+        // where s * rest + t * f^m = g is free of x
+        let (s, t, g) = bezout_in(&rest, &power);
+        scale = scale * g;
+        let (_, mut here, l) = pseudo_div(&(&rem * &s), &power);
+        let mut here_scale = &scale * &l;
+        let (_, next, l) = pseudo_div(&(&rem * &t), &rest);
+        rem = next;
+        scale = scale * l;
+
+        // write `here` in base f, here = c_0 + c_1 f + ..., so that
+        // here / f^m = c_0 / f^m + c_1 / f^(m-1) + ...
+        for k in 0..*m {
+            let (next, digit, l) = pseudo_div(&here, f);
+            here_scale = here_scale * l;
+            if !digit.is_zero() {
+                terms.push(Expr::new(Times::new([over(&digit, &here_scale), f.to_expr().pow(-((m - k) as i64))])) as Expr);
+            }
+            here = next;
+        }
+    }
+
+    Ok(canonicalize(Expr::new(Plus::new(terms))))
+}
+
+/// `(quot, rem, l)` with `l * a = quot * b + rem`, where `rem` has a
+/// lower degree than `b` in the first generator and `l` is a power of
+/// the leading coefficient of `b` in it.
+fn pseudo_div(a: &Poly, b: &Poly) -> (Poly, Poly, Poly) {
+    let db = b.degree(0).unwrap();
+    let lc = b.coeff_in(0, db);
+    let mut quot = Poly::zero(a.gens().to_vec());
+    let mut rem = a.clone();
+    let mut l = Poly::constant(a.gens().to_vec(), Number::Integer(BigInt::one()));
+    while let Some(dr) = rem.degree(0).filter(|&dr| dr >= db) {
+        let mut exps = vec![0; a.gens().len()];
+        exps[0] = dr - db;
+        let shift = Poly::from_terms(a.gens().to_vec(), [(exps, Number::Integer(BigInt::one()))]);
+        let term = rem.coeff_in(0, dr) * shift;
+        quot = &quot * &lc + &term;
+        rem = &rem * &lc - term * b;
+        l = l * &lc;
+    }
+    (quot, rem, l)
+}
+
+/// `(s, t, g)` with `s * a + t * b = g`, where `g` is free of the first
+/// generator, for `a` and `b` coprime as polynomials in it over the
+/// others, by the extended Euclidean algorithm with pseudo-division.
+fn bezout_in(a: &Poly, b: &Poly) -> (Poly, Poly, Poly) {
+    let one = Poly::constant(a.gens().to_vec(), Number::Integer(BigInt::one()));
+    let zero = Poly::zero(a.gens().to_vec());
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    let (mut s0, mut s1) = (one.clone(), zero.clone());
+    let (mut t0, mut t1) = (zero, one);
+    while !r1.is_zero() {
+        let (q, r, l) = pseudo_div(&r0, &r1);
+        let s = &l * &s0 - &q * &s1;
+        let t = &l * &t0 - &q * &t1;
+
+        // take out what the three have in common, to keep them small
+        let common = r.gcd(&s).gcd(&t);
+        r0 = std::mem::replace(&mut r1, r.div_exact(&common).unwrap());
+        s0 = std::mem::replace(&mut s1, s.div_exact(&common).unwrap());
+        t0 = std::mem::replace(&mut t1, t.div_exact(&common).unwrap());
+    }
+    (s0, t0, r0)
+}
+
+/// `p / scale` for a `scale` free of the first generator, with the
+/// number in `scale` divided into the coefficients of `p`.
+fn over(p: &Poly, scale: &Poly) -> Expr {
+    let (c, scale) = scale.primitive();
+    let p = p.scale(&c.pow(&Number::Integer(-BigInt::one())).unwrap());
+    if scale.total_degree() == Some(0) {
+        p.to_expr()
+    } else {
+        cancel(p.to_expr() / scale.to_expr())
+    }
+}
@@ -0,0 +1,221 @@
+use std::{error, fmt};
+
+use num::{BigInt, BigRational, Signed, Zero};
+
+use crate::{Number, convert::{Convert, LaTex}, expr::Expr, number::Sign, polys::{Poly, PolyError}, symbol::Symbol};
+
+/// The number of distinct real roots of the polynomial `poly` in `x`
+/// in `(lower, upper]`, where either bound may be an infinity.
+///
+/// Fails when `poly` isn't a polynomial in `x` with rational
+/// coefficients. The zero polynomial counts as having no roots.
+pub fn count_real_roots(poly: Expr, x: Expr<Symbol>, lower: &Number, upper: &Number) -> Result<usize, PolyError> {
+    let sturm = Sturm::new(poly, x)?;
+    Ok(sturm.count(lower, upper))
+}
+
+/// Disjoint intervals with rational bounds that each hold exactly one
+/// distinct real root of the polynomial `poly` in `x`, in increasing
+/// order.
+///
+/// Each interval is either open, holding its root strictly between
+/// the bounds, or a single point when the root was found exactly. They
+/// are found by bisection, counting the roots of each half with a Sturm
+/// sequence, and can be narrowed with [`refine_root_interval`].
+///
+/// Fails when `poly` isn't a polynomial in `x` with rational
+/// coefficients.
+pub fn real_root_intervals(poly: Expr, x: Expr<Symbol>) -> Result<Vec<(Number, Number)>, PolyError> {
+    let sturm = Sturm::new(poly, x)?;
+    if sturm.seq.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // every root is less than 1 + max |a_i / a_n| in absolute value
+    let f = &sturm.seq[0];
+    let lc = f.leading_coeff().unwrap().to_rational().unwrap();
+    let bound = f.terms().iter().map(|(_, c)| (c.to_rational().unwrap() / &lc).abs()).max().unwrap() + BigInt::from(1);
+
+    let mut res = vec![];
+    let mut stack = vec![(-bound.clone(), bound)];
+    while let Some((a, b)) = stack.pop() {
+        let k = sturm.count_rational(&a, &b);
+        if k == 0 {
+            continue;
+        }
+        if k == 1 {
+            res.push(if sturm.is_root(&b) { (b.clone(), b) } else { (a, b) });
+            continue;
+        }
+
+        let mid = (&a + &b) / BigInt::from(2);
+        stack.push((mid.clone(), b));
+        stack.push((a, mid));
+    }
+
+    res.sort();
+    Ok(res.into_iter().map(|(a, b)| (Number::from_rational(a), Number::from_rational(b))).collect())
+}
+
+/// Why [`refine_root_interval`] could not narrow an interval.
+#[derive(Debug, Clone)]
+pub enum RefineError {
+    /// The polynomial could not be read.
+    Poly(PolyError),
+    /// A bound of the interval isn't a rational number.
+    NotRational(Number),
+    /// The width isn't a positive rational number.
+    Width(Number),
+    /// The interval holds this many distinct roots instead of one.
+    RootCount(usize),
+}
+
+impl fmt::Display for RefineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefineError::Poly(err) => err.fmt(f),
+            RefineError::NotRational(bound) => write!(f, "the bound {} is not a rational number", latex(bound)),
+            RefineError::Width(width) => write!(f, "the width {} is not positive and rational", latex(width)),
+            RefineError::RootCount(k) => write!(f, "the interval holds {} roots instead of one", k),
+        }
+    }
+}
+
+fn latex(n: &Number) -> String {
+    LaTex::convert_to_string(Expr::new(n.clone()))
+}
+
+impl error::Error for RefineError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RefineError::Poly(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<PolyError> for RefineError {
+    fn from(err: PolyError) -> Self {
+        RefineError::Poly(err)
+    }
+}
+
+/// Narrows an interval from [`real_root_intervals`] by bisection until
+/// it is at most `width` wide, keeping its root inside. The result is a
+/// single point when the root is hit exactly.
+///
+/// Fails when `poly` isn't a polynomial in `x` with rational
+/// coefficients, when a bound of `interval` isn't rational, when `width`
+/// isn't positive, or when `interval` doesn't hold exactly one root.
+pub fn refine_root_interval(poly: Expr, x: Expr<Symbol>, interval: (Number, Number), width: &Number) -> Result<(Number, Number), RefineError> {
+    let sturm = Sturm::new(poly, x)?;
+    let rational = |bound: Number| bound.to_rational().ok_or(RefineError::NotRational(bound));
+    let (mut a, mut b) = (rational(interval.0)?, rational(interval.1)?);
+    let width = width.to_rational().filter(Signed::is_positive).ok_or_else(|| RefineError::Width(width.clone()))?;
+
+    let count = if a == b {
+        if sturm.seq.is_empty() || !sturm.is_root(&a) { 0 } else { 1 }
+    } else if sturm.seq.is_empty() {
+        0
+    } else {
+        sturm.count_rational(&a, &b)
+    };
+    if count != 1 {
+        return Err(RefineError::RootCount(count));
+    }
+
+    while &b - &a > width {
+        let mid = (&a + &b) / BigInt::from(2);
+        if sturm.is_root(&mid) {
+            a = mid.clone();
+            b = mid;
+        } else if sturm.count_rational(&a, &mid) == 1 {
+            b = mid;
+        } else {
+            a = mid;
+        }
+    }
+
+    Ok((Number::from_rational(a), Number::from_rational(b)))
+}
+
+/// The Sturm sequence of the square-free part `f` of a polynomial,
+/// `f, f', -rem(f, f'), ...`, whose sign changes count real roots.
+struct Sturm {
+    seq: Vec<Poly>,
+}
+
+impl Sturm {
+    fn new(poly: Expr, x: Expr<Symbol>) -> Result<Self, PolyError> {
+        let f = Poly::from_expr(&poly, &[x.into()])?;
+        if f.degree(0).unwrap_or(0) == 0 {
+            return Ok(Self { seq: vec![] });
+        }
+
+        let df = f.derivative(0);
+        let f = f.div_exact(&f.gcd(&df)).unwrap();
+        let mut seq = vec![f.clone(), f.derivative(0)];
+        loop {
+            let (_, rem) = seq[seq.len() - 2].div_rem(&seq[seq.len() - 1]);
+            if rem.is_zero() {
+                break;
+            }
+            seq.push(-rem);
+        }
+        Ok(Self { seq })
+    }
+
+    fn is_root(&self, x: &BigRational) -> bool {
+        eval(&self.seq[0], x).is_zero()
+    }
+
+    /// The number of sign changes in the sequence at `x`, skipping zeros.
+    fn changes(&self, signs: impl Iterator<Item = i8>) -> usize {
+        let signs: Vec<i8> = signs.filter(|&s| s != 0).collect();
+        signs.windows(2).filter(|w| w[0] != w[1]).count()
+    }
+
+    fn changes_at(&self, x: &Number) -> usize {
+        match x {
+            Number::Infinity(sign) => self.changes(self.seq.iter().map(|p| {
+                let n = p.degree(0).unwrap();
+                let lc = p.leading_coeff().unwrap();
+                let positive = lc.is_positive() == (*sign == Sign::Plus || n % 2 == 0);
+                if positive { 1 } else { -1 }
+            })),
+            _ => self.changes_at_rational(&x.to_rational().unwrap()),
+        }
+    }
+
+    fn changes_at_rational(&self, x: &BigRational) -> usize {
+        self.changes(self.seq.iter().map(|p| {
+            let value = eval(p, x);
+            if value.is_positive() {
+                1
+            } else if value.is_negative() {
+                -1
+            } else {
+                0
+            }
+        }))
+    }
+
+    /// The number of roots in `(a, b]`.
+    fn count(&self, a: &Number, b: &Number) -> usize {
+        if self.seq.is_empty() {
+            return 0;
+        }
+        self.changes_at(a).saturating_sub(self.changes_at(b))
+    }
+
+    fn count_rational(&self, a: &BigRational, b: &BigRational) -> usize {
+        self.changes_at_rational(a).saturating_sub(self.changes_at_rational(b))
+    }
+}
+
+fn eval(p: &Poly, x: &BigRational) -> BigRational {
+    p.terms()
+        .iter()
+        .map(|(e, c)| c.to_rational().unwrap() * num::pow(x.clone(), e[0] as usize))
+        .fold(BigRational::zero(), |acc, t| acc + t)
+}
@@ -0,0 +1,169 @@
+use num::BigInt;
+
+use crate::{Number, expr::Expr, polys::Poly, symbol::Symbol, undefined::UNDEFINED};
+
+/// How [`resultant_with`] computes a resultant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResultantMethod {
+    /// The determinant of the Sylvester matrix, by fraction-free
+    /// Gaussian elimination.
+    Sylvester,
+    /// The last element of the subresultant PRS, which keeps the
+    /// intermediate coefficients small.
+    #[default]
+    Subresultant,
+}
+
+/// The resultant of `p` and `q` as polynomials in `x`, which is zero
+/// exactly when they have a common root, so it eliminates `x` from the
+/// system `p = q = 0`.
+///
+/// The other symbols in `p` and `q` are parameters of the coefficients.
+/// Gives undefined when `p` or `q` isn't a polynomial.
+pub fn resultant(p: Expr, q: Expr, x: Expr<Symbol>) -> Expr {
+    resultant_with(p, q, x, ResultantMethod::default())
+}
+
+/// See [`resultant`] and [`ResultantMethod`].
+pub fn resultant_with(p: Expr, q: Expr, x: Expr<Symbol>, method: ResultantMethod) -> Expr {
+    let Some((polys, v)) = polys_in(&[p, q], x) else {
+        return UNDEFINED.clone();
+    };
+
+    match method {
+        ResultantMethod::Sylvester => sylvester(&polys[0], &polys[1], v),
+        ResultantMethod::Subresultant => subresultant(&polys[0], &polys[1], v),
+    }
+    .to_expr()
+}
+
+/// The discriminant of `p` as a polynomial in `x`, which is zero
+/// exactly when `p` has a repeated root, so `b^2 - 4*a*c` for
+/// `a*x^2 + b*x + c`.
+///
+/// Gives undefined when `p` isn't a polynomial of degree at least one.
+pub fn discriminant(p: Expr, x: Expr<Symbol>) -> Expr {
+    let Some((polys, v)) = polys_in(&[p], x) else {
+        return UNDEFINED.clone();
+    };
+    let p = &polys[0];
+    let Some(n) = p.degree(v).filter(|&n| n > 0) else {
+        return UNDEFINED.clone();
+    };
+
+    // (-1)^(n (n - 1) / 2) / lc(p) * res(p, p')
+    let res = subresultant(p, &p.derivative(v), v);
+    let disc = res.div_exact(&p.coeff_in(v, n)).unwrap();
+    if (n * (n - 1) / 2) % 2 == 1 { -disc } else { disc }.to_expr()
+}
+
+/// `exprs` as polynomials in their generators and `x`, with the index
+/// of `x` among them.
+fn polys_in(exprs: &[Expr], x: Expr<Symbol>) -> Option<(Vec<Poly>, usize)> {
+    let x: Expr = x.into();
+    let mut gens = Poly::from_exprs(exprs, &[]).ok()?.remove(0).gens().to_vec();
+    let v = match gens.iter().position(|g| PartialEq::eq(g, &x)) {
+        Some(v) => v,
+        None => {
+            gens.push(x);
+            gens.len() - 1
+        }
+    };
+    Some((Poly::from_exprs(exprs, &gens).ok()?, v))
+}
+
+fn degree(p: &Poly, v: usize) -> u32 {
+    p.degree(v).unwrap_or(0)
+}
+
+fn one(like: &Poly) -> Poly {
+    Poly::constant(like.gens().to_vec(), Number::Integer(BigInt::from(1))).with_order(like.order())
+}
+
+/// The determinant of the Sylvester matrix of `p` and `q` in `v`, by
+/// Bareiss' algorithm, where every division is exact.
+fn sylvester(p: &Poly, q: &Poly, v: usize) -> Poly {
+    if p.is_zero() || q.is_zero() {
+        return Poly::zero(p.gens().to_vec());
+    }
+
+    let (m, n) = (degree(p, v) as usize, degree(q, v) as usize);
+    let size = m + n;
+    let zero = Poly::zero(p.gens().to_vec());
+    let mut matrix = vec![vec![zero.clone(); size]; size];
+    for i in 0..n {
+        for k in 0..=m {
+            matrix[i][i + k] = p.coeff_in(v, (m - k) as u32);
+        }
+    }
+    for i in 0..m {
+        for k in 0..=n {
+            matrix[n + i][i + k] = q.coeff_in(v, (n - k) as u32);
+        }
+    }
+
+    let mut negate = false;
+    let mut prev = one(p);
+    for k in 0..size {
+        if matrix[k][k].is_zero() {
+            let Some(i) = (k + 1..size).find(|&i| !matrix[i][k].is_zero()) else {
+                return zero;
+            };
+            matrix.swap(k, i);
+            negate = !negate;
+        }
+
+        for i in k + 1..size {
+            for j in k + 1..size {
+                let entry = &matrix[i][j] * &matrix[k][k] - &matrix[i][k] * &matrix[k][j];
+                matrix[i][j] = entry.div_exact(&prev).unwrap();
+            }
+        }
+        prev = matrix[k][k].clone();
+    }
+
+    if negate { -prev } else { prev }
+}
+
+/// The resultant of `a` and `b` in `v` by the subresultant PRS, as in
+/// Cohen's algorithm 3.3.7.
+fn subresultant(a: &Poly, b: &Poly, v: usize) -> Poly {
+    if a.is_zero() || b.is_zero() {
+        return Poly::zero(a.gens().to_vec());
+    }
+
+    let (mut a, mut b) = (a.clone(), b.clone());
+    let mut negate = false;
+    if degree(&a, v) < degree(&b, v) {
+        std::mem::swap(&mut a, &mut b);
+        negate = degree(&a, v) % 2 == 1 && degree(&b, v) % 2 == 1;
+    }
+    if degree(&b, v) == 0 {
+        let res = b.pow(degree(&a, v));
+        return if negate { -res } else { res };
+    }
+
+    let (mut g, mut h) = (one(&a), one(&a));
+    loop {
+        let (da, db) = (degree(&a, v), degree(&b, v));
+        let delta = da - db;
+        if da % 2 == 1 && db % 2 == 1 {
+            negate = !negate;
+        }
+
+        let r = a.prem(&b, v);
+        if r.is_zero() {
+            return Poly::zero(a.gens().to_vec());
+        }
+        a = b;
+        b = r.div_exact(&(&g * &h.pow(delta))).unwrap();
+        g = a.coeff_in(v, degree(&a, v));
+        h = if delta == 0 { h } else { g.pow(delta).div_exact(&h.pow(delta - 1)).unwrap() };
+
+        if degree(&b, v) == 0 {
+            let da = degree(&a, v);
+            let res = b.pow(da).div_exact(&h.pow(da - 1)).unwrap();
+            return if negate { -res } else { res };
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::ops::ControlFlow;
+
+use crate::{Number, canonicalize::canonicalize, expr::Expr, fold::{Transformer, bottom_up}, functions::{Acosh, Asinh, Atanh, Cosh, Exp, Log, Plus, Pow, Sinh, Tanh, acosh, asinh, cosh, exp, log, sinh}, undefined::UNDEFINED};
+
+/// The form that [`rewrite`] rewrites an expression into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rewrite {
+    /// Hyperbolic functions and their inverses in terms of `exp` and `log`.
+    ExpLog,
+    /// `exp`, and the `log` forms of the inverse hyperbolic functions,
+    /// in terms of hyperbolic functions.
+    Hyperbolic,
+}
+
+pub fn rewrite(expr: Expr, target: Rewrite) -> Expr {
+    match bottom_up(expr.rf(), &mut Rewriter { target }) {
+        ControlFlow::Continue(res) => canonicalize(res),
+        ControlFlow::Break(_) => UNDEFINED.clone(),
+    }
+}
+
+struct Rewriter {
+    target: Rewrite,
+}
+
+/// If `expr` is `t + sqrt(t^2 + offset)`, returns `t`.
+fn match_sqrt_sum(expr: &Expr, offset: i32) -> Option<Expr> {
+    let plus = expr.downcast::<Plus>()?;
+    let [a, b] = plus.terms() else {
+        return None;
+    };
+
+    let half = Number::rational(1, 2);
+    for (t, radical) in [(a, b), (b, a)] {
+        if let Some(pow) = radical.downcast::<Pow>() {
+            if pow.exp().downcast::<Number>() == Some(&half)
+                && PartialEq::eq(&pow.base().into_expr(), &canonicalize(t.clone().pow(2) + offset))
+            {
+                return Some(t.clone());
+            }
+        }
+    }
+
+    None
+}
+
+impl Rewriter {
+    /// `exp` and the inverse hyperbolic `log` forms in terms of hyperbolic functions.
+    fn to_hyperbolic(expr: &Expr) -> Option<Expr> {
+        if let Some(exp) = expr.downcast::<Exp>() {
+            // e^x = cosh(x) + sinh(x)
+            let x = exp.arg();
+            return Some(cosh(x) + sinh(x));
+        }
+
+        let log = expr.downcast::<Log>().filter(|log| log.is_natural())?;
+        let arg = canonicalize(log.arg().into_expr());
+        if let Some(t) = match_sqrt_sum(&arg, 1) {
+            // ln(t + sqrt(t^2 + 1)) = asinh(t)
+            Some(asinh(t).into())
+        } else {
+            // ln(t + sqrt(t^2 - 1)) = acosh(t)
+            match_sqrt_sum(&arg, -1).map(|t| acosh(t).into())
+        }
+    }
+
+    /// The hyperbolic functions and their inverses in terms of `exp` and `log`.
+    fn to_exp_log(expr: &Expr) -> Option<Expr> {
+        let half: Expr = Expr::new(Number::rational(1, 2));
+
+        Some(if let Some(sinh) = expr.downcast::<Sinh>() {
+            // (e^x - e^-x) / 2
+            let x = sinh.arg();
+            (exp(x) - exp(-x)) / 2
+        } else if let Some(cosh) = expr.downcast::<Cosh>() {
+            // (e^x + e^-x) / 2
+            let x = cosh.arg();
+            (exp(x) + exp(-x)) / 2
+        } else if let Some(tanh) = expr.downcast::<Tanh>() {
+            // (e^x - e^-x) / (e^x + e^-x)
+            let x = tanh.arg();
+            (exp(x) - exp(-x)) / (exp(x) + exp(-x))
+        } else if let Some(asinh) = expr.downcast::<Asinh>() {
+            // ln(x + sqrt(x^2 + 1))
+            let x = asinh.arg();
+            log(x + (x.pow(2) + 1).pow(half)).into()
+        } else if let Some(acosh) = expr.downcast::<Acosh>() {
+            // ln(x + sqrt(x^2 - 1))
+            let x = acosh.arg();
+            log(x + (x.pow(2) - 1).pow(half)).into()
+        } else if let Some(atanh) = expr.downcast::<Atanh>() {
+            // (ln(1 + x) - ln(1 - x)) / 2
+            let x = atanh.arg();
+            (log(x + 1) - log(-x + 1)) / 2
+        } else {
+            return None;
+        })
+    }
+}
+
+impl Transformer for Rewriter {
+    fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+        let rewritten = match self.target {
+            Rewrite::ExpLog => Self::to_exp_log(&expr),
+            Rewrite::Hyperbolic => Self::to_hyperbolic(&expr),
+        };
+
+        ControlFlow::Continue(rewritten.unwrap_or(expr))
+    }
+}
@@ -0,0 +1,344 @@
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use num::{BigInt, BigRational, Integer, One, Signed, Zero};
+
+use crate::{Number, basic::Basic, canonicalize::canonicalize, constant::I, convert::{Convert, LaTex}, expr::{Expr, ExprRef}, factor::factor_list, polys::{Poly, PolyError}, symbol::Symbol, visitor::Visitor};
+
+/// The `index`th root of the irreducible polynomial `poly` in `x`, for
+/// degrees that have no formula in radicals. The roots are numbered in
+/// no particular order, from zero.
+///
+/// It is a leaf, since substituting into `poly` would change which
+/// number it stands for.
+#[derive(Debug, Clone)]
+pub struct RootOf {
+    poly: Expr,
+    x: Expr<Symbol>,
+    index: u32,
+}
+
+impl RootOf {
+    pub fn poly(&self) -> &Expr {
+        &self.poly
+    }
+
+    pub fn x(&self) -> &Expr<Symbol> {
+        &self.x
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl Basic for RootOf {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_other(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        other.downcast::<Self>().is_some_and(|other| {
+            PartialEq::eq(&self.poly, &other.poly) && PartialEq::eq(&self.x, &other.x) && self.index == other.index
+        })
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| {
+            Ord::cmp(&self.poly, &other.poly)
+                .then_with(|| Ord::cmp(&self.x, &other.x))
+                .then_with(|| self.index.cmp(&other.index))
+        })
+    }
+
+    fn latex(&self, _args: &[String]) -> Option<String> {
+        Some(format!("\\operatorname{{RootOf}}\\left({}, {}\\right)", LaTex::convert_to_string(self.poly.clone()), self.index))
+    }
+}
+
+/// The roots of the polynomial `poly` in `x` with their multiplicities,
+/// as exact expressions.
+///
+/// Rational roots come first, in increasing order, found by the rational
+/// root theorem, or from the linear factors of `poly` when its lowest
+/// and leading coefficients are too large to enumerate the divisors of.
+/// What is left is factored, and each factor is solved by the quadratic
+/// formula, Cardano's formula for cubics or Ferrari's for quartics.
+/// Factors of higher degree give [`RootOf`]s.
+///
+/// Fails when `poly` isn't a polynomial in `x` with rational
+/// coefficients. The zero polynomial has no roots listed.
+pub fn roots(poly: Expr, x: Expr<Symbol>) -> Result<Vec<(Expr, u32)>, PolyError> {
+    let p = Poly::from_expr(&poly, &[x.clone().into()])?;
+    Ok(roots_of(dense(&p), &x))
+}
+
+/// The coefficients of the polynomial in one generator `p`, lowest
+/// degree first.
+fn dense(p: &Poly) -> Vec<BigRational> {
+    let n = p.degree(0).map_or(0, |n| n as usize + 1);
+    (0..n as u32).map(|k| p.coeff(&[k]).to_rational().unwrap()).collect()
+}
+
+/// The largest lowest and leading coefficient whose divisors are
+/// enumerated for the rational root theorem. Above it, the rational
+/// roots come from the linear factors instead.
+const DIVISOR_BOUND: u64 = 1_000_000_000_000;
+
+fn roots_of(mut f: Vec<BigRational>, x: &Expr<Symbol>) -> Vec<(Expr, u32)> {
+    if f.len() < 2 {
+        return vec![];
+    }
+
+    let mut rational = vec![];
+    for r in rational_roots(&f).unwrap_or_default() {
+        let mut m = 0;
+        while f.len() > 1 && eval(&f, &r).is_zero() {
+            f = divide_linear(&f, &r);
+            m += 1;
+        }
+        rational.push((r, m));
+    }
+
+    let mut res = vec![];
+    if f.len() > 1 {
+        let rest = Poly::from_terms(vec![x.clone().into()], f.iter().enumerate().map(|(k, c)| (vec![k as u32], Number::from_rational(c.clone()))));
+        let (_, factors) = factor_list(rest.to_expr());
+        for (g, m) in factors {
+            let g = dense(&Poly::from_expr(&g, &[x.clone().into()]).unwrap());
+            let lc = g.last().unwrap().clone();
+            let g: Vec<BigRational> = g.iter().map(|c| c / &lc).collect();
+            let solved = match g.len() - 1 {
+                // only when the coefficients were too large to enumerate
+                1 => {
+                    rational.push((-&g[0], m));
+                    continue;
+                }
+                2 => quadratic(&g[1], &g[0]),
+                3 => cubic(&g[2], &g[1], &g[0]),
+                4 => quartic(&g[3], &g[2], &g[1], &g[0], x),
+                n => {
+                    let poly = Poly::from_terms(vec![x.clone().into()], g.iter().enumerate().map(|(k, c)| (vec![k as u32], Number::from_rational(c.clone()))));
+                    (0..n as u32)
+                        .map(|index| Expr::new(RootOf { poly: poly.to_expr(), x: x.clone(), index }) as Expr)
+                        .collect()
+                }
+            };
+            res.extend(solved.into_iter().map(|root| (root, m)));
+        }
+    }
+
+    rational.sort();
+    rational.into_iter().map(|(r, m)| (number(r), m)).chain(res).collect()
+}
+
+/// The rational roots of `f` by the rational root theorem, in
+/// increasing order: each is `±a/b` where `a` divides the lowest
+/// nonzero coefficient and `b` the leading one, once cleared of
+/// denominators. `None` when either is larger than [`DIVISOR_BOUND`].
+fn rational_roots(f: &[BigRational]) -> Option<Vec<BigRational>> {
+    let denom = f.iter().fold(BigInt::one(), |l, c| l.lcm(c.denom()));
+    let ints: Vec<BigInt> = f.iter().map(|c| (c * &denom).to_integer()).collect();
+    let lowest = ints.iter().position(|c| !c.is_zero()).unwrap();
+
+    let mut res = vec![];
+    if lowest > 0 {
+        res.push(BigRational::zero());
+    }
+    if lowest + 1 == ints.len() {
+        return Some(res);
+    }
+
+    let bound = BigInt::from(DIVISOR_BOUND);
+    if ints[lowest].abs() > bound || ints.last().unwrap().abs() > bound {
+        return None;
+    }
+    let numers = divisors(&ints[lowest]);
+    let denoms = divisors(ints.last().unwrap());
+    for a in &numers {
+        for b in &denoms {
+            for r in [BigRational::new(a.clone(), b.clone()), BigRational::new(-a, b.clone())] {
+                if !res.contains(&r) && eval(f, &r).is_zero() {
+                    res.push(r);
+                }
+            }
+        }
+    }
+    res.sort();
+    Some(res)
+}
+
+/// The positive divisors of `n`, by trial division up to its square root.
+fn divisors(n: &BigInt) -> Vec<BigInt> {
+    let n = n.abs();
+    let mut small = vec![];
+    let mut large = vec![];
+    let mut d = BigInt::one();
+    while &d * &d <= n {
+        if (&n % &d).is_zero() {
+            let other = &n / &d;
+            if other != d {
+                large.push(other);
+            }
+            small.push(d.clone());
+        }
+        d += 1;
+    }
+    small.extend(large.into_iter().rev());
+    small
+}
+
+fn eval(f: &[BigRational], x: &BigRational) -> BigRational {
+    f.iter().rev().fold(BigRational::zero(), |acc, c| acc * x + c)
+}
+
+/// `f / (x - r)` for a root `r` of `f`, by synthetic division.
+fn divide_linear(f: &[BigRational], r: &BigRational) -> Vec<BigRational> {
+    let mut quot = vec![BigRational::zero(); f.len() - 1];
+    let mut carry = BigRational::zero();
+    for k in (1..f.len()).rev() {
+        carry = &carry * r + &f[k];
+        quot[k - 1] = carry.clone();
+    }
+    quot
+}
+
+fn number(r: BigRational) -> Expr {
+    Expr::new(Number::from_rational(r))
+}
+
+fn rational(numer: i64, denom: i64) -> BigRational {
+    BigRational::new(numer.into(), denom.into())
+}
+
+/// The `k`th root of `r`, for `k` of 2 or 3, with the largest `k`th
+/// power taken out, so `sqrt(12)` is `2 * sqrt(3)`. The square root of
+/// a negative number is `i` times that of its negation, and the cube
+/// root is the real one.
+fn radical(r: &BigRational, k: u32) -> Expr {
+    if r.is_negative() {
+        let root = radical(&-r, k);
+        return canonicalize(if k == 2 { root * I.clone() } else { -root });
+    }
+
+    // r = n / d = n * d^(k-1) / d^k
+    let n = r.numer() * num::pow(r.denom().clone(), k as usize - 1);
+    let (outside, inside) = split_power(&n, k);
+    let coef = number(BigRational::new(outside, r.denom().clone()));
+    canonicalize(coef * Expr::new(Number::Integer(inside)).pow(Expr::new(Number::rational(1, k))))
+}
+
+/// Splits `n` into `a^k * b`, returning `(a, b)`, taking out the
+/// factors that trial division finds below a bound, and `n` itself when
+/// it is a perfect power.
+fn split_power(n: &BigInt, k: u32) -> (BigInt, BigInt) {
+    let root = n.nth_root(k);
+    if num::pow(root.clone(), k as usize) == *n {
+        return (root, BigInt::one());
+    }
+
+    let (mut outside, mut inside) = (BigInt::one(), n.clone());
+    let mut p = BigInt::from(2);
+    while p < BigInt::from(10_000) && num::pow(p.clone(), k as usize) <= inside {
+        let power = num::pow(p.clone(), k as usize);
+        while (&inside % &power).is_zero() {
+            inside /= &power;
+            outside *= &p;
+        }
+        p += 1;
+    }
+    (outside, inside)
+}
+
+fn sqrt(e: Expr) -> Expr {
+    match e.downcast::<Number>().and_then(Number::to_rational) {
+        Some(r) => radical(&r, 2),
+        None => canonicalize(e.pow(Expr::new(Number::rational(1, 2)))),
+    }
+}
+
+fn cbrt(e: Expr) -> Expr {
+    match e.downcast::<Number>().and_then(Number::to_rational) {
+        Some(r) => radical(&r, 3),
+        None => canonicalize(e.pow(Expr::new(Number::rational(1, 3)))),
+    }
+}
+
+/// The roots of `x^2 + b*x + c`.
+fn quadratic(b: &BigRational, c: &BigRational) -> Vec<Expr> {
+    // x = -b/2 ± sqrt(b^2/4 - c)
+    let root = sqrt(number(b * b / BigInt::from(4) - c));
+    let mid = number(-b / BigInt::from(2));
+    vec![canonicalize(&mid - &root), canonicalize(mid + root)]
+}
+
+/// The roots of `x^3 + a*x^2 + b*x + c` by Cardano's formula.
+fn cubic(a: &BigRational, b: &BigRational, c: &BigRational) -> Vec<Expr> {
+    // x = t - a/3, where t^3 + p*t + q = 0
+    let p = b - a * a / BigInt::from(3);
+    let q = a * a * a * rational(2, 27) - a * b / BigInt::from(3) + c;
+    let shift = number(-a / BigInt::from(3));
+
+    // the cube roots of unity
+    let half_sqrt3 = canonicalize(radical(&rational(3, 1), 2) / 2);
+    let omega = [
+        Expr::from(1i32),
+        canonicalize(number(rational(-1, 2)) + &half_sqrt3 * I.clone()),
+        canonicalize(number(rational(-1, 2)) - &half_sqrt3 * I.clone()),
+    ];
+
+    let roots: Vec<Expr> = if p.is_zero() {
+        let u = cbrt(number(-q));
+        omega.iter().map(|w| w * &u).collect()
+    } else {
+        // t = w*u - p / (3*w*u), where u^3 = -q/2 + sqrt(q^2/4 + p^3/27)
+        let d = &q * &q / BigInt::from(4) + &p * &p * &p / BigInt::from(27);
+        let u = cbrt(canonicalize(number(-&q / BigInt::from(2)) + sqrt(number(d))));
+        omega
+            .iter()
+            .map(|w| {
+                let wu = w * &u;
+                &wu - number(p.clone()) / (wu.clone() * 3)
+            })
+            .collect()
+    };
+    roots.into_iter().map(|t| canonicalize(t + &shift)).collect()
+}
+
+/// The roots of `x^4 + a*x^3 + b*x^2 + c*x + d` by Ferrari's method.
+fn quartic(a: &BigRational, b: &BigRational, c: &BigRational, d: &BigRational, x: &Expr<Symbol>) -> Vec<Expr> {
+    // x = y - a/4, where y^4 + p*y^2 + q*y + r = 0
+    let a2 = a * a;
+    let p = b - &a2 * rational(3, 8);
+    let q = c - a * b / BigInt::from(2) + &a2 * a / BigInt::from(8);
+    let r = d - a * c / BigInt::from(4) + &a2 * b / BigInt::from(16) - &a2 * &a2 * rational(3, 256);
+    let shift = number(-a / BigInt::from(4));
+
+    let ys: Vec<Expr> = if q.is_zero() {
+        // y^2 = (-p ± sqrt(p^2 - 4r)) / 2
+        let mut ys = vec![];
+        for z in quadratic(&p, &r) {
+            let y = sqrt(z);
+            ys.push(canonicalize(-&y));
+            ys.push(y);
+        }
+        ys
+    } else {
+        // a root m of the resolvent m^3 + p*m^2 + (p^2/4 - r)*m - q^2/8,
+        // none of which is zero
+        let resolvent = vec![-&q * &q / BigInt::from(8), &p * &p / BigInt::from(4) - &r, p.clone(), BigRational::one()];
+        let m = roots_of(resolvent, x).remove(0).0;
+
+        // y = (s*sqrt(2m) ± sqrt(-(2p + 2m + s*sqrt(2)*q/sqrt(m)))) / 2 for s = ±1
+        let sqrt_2m = sqrt(canonicalize(&m * 2));
+        let twist = canonicalize(radical(&rational(2, 1), 2) * number(q.clone()) / sqrt(m.clone()));
+        let mut ys = vec![];
+        for s in [-1i32, 1] {
+            let inner = sqrt(canonicalize(-(number(&p * BigInt::from(2)) + &m * 2 + &twist * s)));
+            let outer = &sqrt_2m * s;
+            ys.push(canonicalize((&outer - &inner) / 2));
+            ys.push(canonicalize((outer + inner) / 2));
+        }
+        ys
+    };
+    ys.into_iter().map(|y| canonicalize(y + &shift)).collect()
+}
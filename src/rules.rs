@@ -0,0 +1,201 @@
+use std::{fmt, ops::ControlFlow, sync::Arc};
+
+use string_cache::DefaultAtom;
+
+use crate::{canonicalize::canonicalize, expr::Expr, fold::{Transformer, bottom_up}, pattern::{Bindings, match_where}};
+
+type Condition = Arc<dyn Fn(&Bindings) -> bool + Send + Sync>;
+
+/// A rewrite rule `lhs -> rhs`, where the wilds of `lhs` are replaced
+/// in `rhs` by what they matched.
+#[derive(Clone)]
+pub struct Rule {
+    name: DefaultAtom,
+    lhs: Expr,
+    rhs: Expr,
+    condition: Option<Condition>,
+}
+
+impl Rule {
+    pub fn new(name: &str, lhs: impl Into<Expr>, rhs: impl Into<Expr>) -> Self {
+        Self {
+            name: DefaultAtom::from(name),
+            lhs: canonicalize(lhs.into()),
+            rhs: rhs.into(),
+            condition: None,
+        }
+    }
+
+    /// Only fires the rule for matches where `condition` holds.
+    pub fn when(mut self, condition: impl Fn(&Bindings) -> bool + Send + Sync + 'static) -> Self {
+        self.condition = Some(Arc::new(condition));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn lhs(&self) -> &Expr {
+        &self.lhs
+    }
+
+    pub fn rhs(&self) -> &Expr {
+        &self.rhs
+    }
+
+    /// Rewrites `expr` with the first match of `lhs` that satisfies the
+    /// condition, or returns `None` when there is none.
+    pub fn apply(&self, expr: &Expr) -> Option<Expr> {
+        match_where(&self.lhs, expr, |b| self.condition.as_ref().is_none_or(|condition| condition(b)))
+            .map(|b| b.instantiate(self.rhs.clone()))
+    }
+}
+
+impl fmt::Debug for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rule")
+            .field("name", &self.name)
+            .field("lhs", &self.lhs)
+            .field("rhs", &self.rhs)
+            .field("conditional", &self.condition.is_some())
+            .finish()
+    }
+}
+
+/// Why [`RuleSet::apply`] stopped rewriting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// No rule fires anywhere in the expression.
+    FixedPoint,
+    /// The rules led back to an expression that was already seen.
+    Cycle,
+    /// The rules fired `max_steps` times.
+    StepLimit,
+}
+
+/// One rule firing on a subexpression.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub rule: DefaultAtom,
+    pub before: Expr,
+    pub after: Expr,
+}
+
+/// The result of [`RuleSet::apply`].
+#[derive(Debug, Clone)]
+pub struct Rewritten {
+    pub expr: Expr,
+    pub trace: Vec<Step>,
+    pub stop: Stop,
+}
+
+/// A list of rules that are applied together until none of them fire.
+///
+/// When several rules match the same subexpression, the one that was
+/// added first wins.
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    max_steps: usize,
+}
+
+impl RuleSet {
+    pub fn new(rules: impl IntoIterator<Item = Rule>) -> Self {
+        Self {
+            rules: rules.into_iter().collect(),
+            max_steps: 1000,
+        }
+    }
+
+    /// Limits how many times rules may fire in a single call to `apply`.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn push(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Rewrites `expr` with the rules, bottom-up, one pass after another
+    /// until it stops changing.
+    ///
+    /// Rewriting also stops, keeping the last expression reached, when
+    /// a pass gives back an expression seen after an earlier pass, or when
+    /// rules have fired `max_steps` times.
+    pub fn apply(&self, expr: Expr) -> Rewritten {
+        let mut pass = Pass {
+            rules: &self.rules,
+            steps_left: self.max_steps,
+            trace: vec![],
+        };
+
+        let mut expr = canonicalize(expr);
+        let mut seen = vec![expr.clone()];
+        let stop = loop {
+            let fired = pass.trace.len();
+            let next = match bottom_up(expr.rf(), &mut pass) {
+                ControlFlow::Continue(next) => canonicalize(next),
+                ControlFlow::Break(()) => unreachable!(),
+            };
+
+            if pass.trace.len() == fired || PartialEq::eq(&next, &expr) {
+                break Stop::FixedPoint;
+            }
+
+            expr = next;
+            if pass.steps_left == 0 {
+                break Stop::StepLimit;
+            }
+            if seen.iter().any(|old| PartialEq::eq(old, &expr)) {
+                break Stop::Cycle;
+            }
+            seen.push(expr.clone());
+        };
+
+        Rewritten {
+            expr,
+            trace: pass.trace,
+            stop,
+        }
+    }
+}
+
+/// Fires the first matching rule on each node, once per pass.
+struct Pass<'a> {
+    rules: &'a [Rule],
+    steps_left: usize,
+    trace: Vec<Step>,
+}
+
+impl Transformer for Pass<'_> {
+    fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+        if self.steps_left == 0 {
+            return ControlFlow::Continue(expr);
+        }
+
+        for rule in self.rules {
+            let Some(after) = rule.apply(&expr) else {
+                continue;
+            };
+            if PartialEq::eq(&after, &canonicalize(expr.clone())) {
+                continue;
+            }
+
+            self.steps_left -= 1;
+            self.trace.push(Step {
+                rule: rule.name.clone(),
+                before: expr,
+                after: after.clone(),
+            });
+            return ControlFlow::Continue(after);
+        }
+
+        ControlFlow::Continue(expr)
+    }
+}
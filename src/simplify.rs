@@ -1,35 +1,554 @@
-use std::ops::ControlFlow;
-
-use crate::{Number, expr::Expr, functions::{Plus, Times}, symbol::Symbol, undefined::Undefined, visitor::Visitor};
+use std::{cmp::Ordering, collections::BTreeMap, mem, ops::ControlFlow};
 
+use crate::{Number, basic::Basic, canonicalize::{Canonicalize, canonicalize}, expr::{Expr, ExprRef}, fold::{Transformer, bottom_up, rebuild}, functions::{Plus, Pow, Times}, number::ONE, undefined::{Undefined, UNDEFINED}, visitor::Visitor};
 
+/// Simplifies `expr` to the smallest equivalent form that the algebraic
+/// rewrites can find, measured by [`AstSize`].
 pub fn simplify(expr: Expr) -> Expr {
-    todo!()
+    simplify_with(expr, &mut AstSize)
+}
+
+/// Simplifies `expr` to the cheapest equivalent form under `cost`.
+///
+/// Rather than rewriting greedily, every form reached by the rewrites is
+/// kept in an e-graph, so a rewrite that makes the expression larger
+/// can still lead to a smaller one later on. The rewrites are
+/// distributivity and factoring, associativity, the power laws and
+/// whatever `canonicalize` does to a single node, along with collecting
+/// like terms. Commutativity needs no rewrite, since the terms of `Plus`
+/// and `Times` are kept sorted.
+pub fn simplify_with<C: CostFunction + ?Sized>(expr: Expr, cost: &mut C) -> Expr {
+    let expr = collect_like_terms(expr);
+    if expr.is::<Undefined>() {
+        return expr;
+    }
+
+    let mut egraph = EGraph::default();
+    let root = egraph.add_expr(expr.rf());
+    egraph.saturate();
+    canonicalize(egraph.extract(root, cost))
+}
+
+/// Collects the like terms of every sum in `expr`, so `2*x + 3*x`
+/// becomes `5*x` and `x - x` becomes `0`.
+///
+/// Terms are alike when they are the same apart from a number factor.
+pub fn collect_like_terms(expr: Expr) -> Expr {
+    match bottom_up(expr.rf(), &mut CollectLikeTerms) {
+        ControlFlow::Continue(res) => res,
+        ControlFlow::Break(_) => UNDEFINED.clone(),
+    }
+}
+
+struct CollectLikeTerms;
+
+impl Transformer for CollectLikeTerms {
+    fn transform(&mut self, expr: Expr) -> ControlFlow<(), Expr> {
+        let expr = Canonicalize.transform(expr)?;
+        let Some(plus) = expr.downcast::<Plus>() else {
+            return ControlFlow::Continue(expr);
+        };
+
+        let mut like: Vec<(Expr, Number)> = vec![];
+        for term in plus.terms() {
+            let (coef, rest) = split_coefficient(term);
+            match like.iter_mut().find(|(other, _)| PartialEq::eq(other, &rest)) {
+                Some((_, sum)) => *sum = &*sum + &coef,
+                None => like.push((rest, coef)),
+            }
+        }
+
+        if like.len() == plus.terms().len() {
+            return ControlFlow::Continue(expr);
+        }
+
+        let terms: Vec<Expr> = like
+            .into_iter()
+            .map(|(rest, coef)| Expr::new(Times::new([Expr::new(coef), rest])) as Expr)
+            .collect();
+        ControlFlow::Continue(canonicalize(Expr::new(Plus::new(terms))))
+    }
 }
 
-struct SimplifyVisitor {
-    res: Expr,
+/// Splits a canonical term into its number factor and the rest, so
+/// `3 * x * y` gives `(3, x * y)` and `x` gives `(1, x)`.
+fn split_coefficient(term: &Expr) -> (Number, Expr) {
+    if let Some(n) = term.downcast::<Number>() {
+        return (n.clone(), ONE.clone());
+    }
+
+    if let Some(times) = term.downcast::<Times>() {
+        let (numbers, rest): (Vec<&Expr>, Vec<&Expr>) = times.terms().iter().partition(|f| f.is::<Number>());
+        if let [n] = numbers[..] {
+            let rest = match rest[..] {
+                [factor] => factor.clone(),
+                _ => Expr::new(Times::new(rest.into_iter().cloned())),
+            };
+            return (n.downcast::<Number>().unwrap().clone(), rest);
+        }
+    }
+
+    ((**ONE).clone(), term.clone())
 }
 
-impl Visitor for SimplifyVisitor {
-    fn visit_undefined(&mut self, undef: &Undefined) -> ControlFlow<()> {
-        // res = ;
-        ControlFlow::Continue(())
+/// Assigns a cost to each node, given the costs of its args.
+///
+/// The args of `node` are placeholders, so only its type and any data
+/// of its own, such as the value of a number, should be looked at. The
+/// cost of a node has to be greater than the cost of each of its args.
+pub trait CostFunction {
+    type Cost: PartialOrd + Clone;
+
+    fn cost(&mut self, node: ExprRef, args: &[Self::Cost]) -> Self::Cost;
+}
+
+/// Counts the nodes of an expression.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AstSize;
+
+impl CostFunction for AstSize {
+    type Cost = usize;
+
+    fn cost(&mut self, _node: ExprRef, args: &[usize]) -> usize {
+        args.iter().sum::<usize>() + 1
+    }
+}
+
+/// How many rounds of rewrites saturation runs for at most.
+const MAX_ITERATIONS: usize = 8;
+/// Saturation stops early once the e-graph holds this many nodes.
+const MAX_NODES: usize = 2_000;
+
+type Id = usize;
+
+/// A placeholder for an e-class, standing in for the args of an e-node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Class(Id);
+
+fn class(id: Id) -> Expr {
+    Expr::new(Class(id))
+}
+
+impl Basic for Class {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_other(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        other.downcast::<Self>() == Some(self)
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
+    }
+}
+
+/// The e-classes that the args of `node` point to. Leaves, and nodes
+/// that can't be rebuilt from new args, have none.
+fn children(node: &Expr) -> Vec<Id> {
+    node.args().iter().filter_map(|arg| arg.downcast::<Class>().map(|c| c.0)).collect()
+}
+
+/// A set of equivalence classes of expressions. Each e-class holds
+/// e-nodes, which are expressions whose args are other e-classes.
+#[derive(Default)]
+struct EGraph {
+    parents: Vec<Id>,
+    nodes: Vec<Vec<Expr>>,
+    memo: BTreeMap<Expr, Id>,
+}
+
+impl EGraph {
+    fn find(&self, mut id: Id) -> Id {
+        while self.parents[id] != id {
+            id = self.parents[id];
+        }
+        id
+    }
+
+    fn classes(&self) -> Vec<Id> {
+        (0..self.parents.len()).filter(|&id| self.find(id) == id).collect()
+    }
+
+    /// The number of distinct e-nodes.
+    fn size(&self) -> usize {
+        self.memo.len()
+    }
+
+    /// Points the args of `node` at the current representative of their e-class.
+    fn canonical_node(&self, node: &Expr) -> Expr {
+        let args = node.args();
+        if args.is_empty() || !args.iter().all(|arg| arg.is::<Class>()) {
+            return node.clone();
+        }
+
+        let args = args.iter().map(|arg| class(self.find(arg.downcast::<Class>().unwrap().0))).collect();
+        rebuild(node.rf(), args)
+    }
+
+    fn add_node(&mut self, node: Expr) -> Id {
+        let node = self.canonical_node(&node);
+        if let Some(&id) = self.memo.get(&node) {
+            return self.find(id);
+        }
+
+        let id = self.parents.len();
+        self.parents.push(id);
+        self.nodes.push(vec![node.clone()]);
+        self.memo.insert(node, id);
+        id
+    }
+
+    /// Adds `expr` and all of its subexpressions, where any `Class`
+    /// leaves refer to e-classes that are already in the graph.
+    fn add_expr(&mut self, expr: ExprRef) -> Id {
+        if let Some(c) = expr.downcast::<Class>() {
+            return self.find(c.0);
+        }
+
+        let args = expr.args();
+        if args.is_empty() {
+            return self.add_node(expr.into_expr());
+        }
+
+        let args = args.iter().map(|arg| class(self.add_expr(arg.rf()))).collect();
+        match expr.with_args(args) {
+            Some(node) => self.add_node(node),
+            // a node that can't be rebuilt is kept whole
+            None => self.add_node(expr.into_expr()),
+        }
+    }
+
+    fn union(&mut self, a: Id, b: Id) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        self.parents[b] = a;
+        let nodes = mem::take(&mut self.nodes[b]);
+        self.nodes[a].extend(nodes);
+        true
+    }
+
+    /// Restores congruence after unions: e-nodes that have become equal
+    /// because their args were merged put their e-classes together too.
+    fn rebuild(&mut self) {
+        loop {
+            self.memo.clear();
+            let mut pending = vec![];
+            for id in self.classes() {
+                let mut nodes: Vec<Expr> = self.nodes[id].iter().map(|node| self.canonical_node(node)).collect();
+                nodes.sort();
+                nodes.dedup_by(|a, b| PartialEq::eq(&*a, &*b));
+
+                for node in &nodes {
+                    match self.memo.get(node) {
+                        Some(&other) => pending.push((other, id)),
+                        None => {
+                            self.memo.insert(node.clone(), id);
+                        }
+                    }
+                }
+                self.nodes[id] = nodes;
+            }
+
+            let mut changed = false;
+            for (a, b) in pending {
+                changed |= self.union(a, b);
+            }
+            if !changed {
+                break;
+            }
+        }
     }
 
-    fn visit_number(&mut self, n: &Number) -> ControlFlow<()> {
-        todo!()
+    fn saturate(&mut self) {
+        for _ in 0..MAX_ITERATIONS {
+            let (mut folds, mut found) = (vec![], vec![]);
+            for id in self.classes() {
+                for node in &self.nodes[id] {
+                    self.folds(node, &mut |expr| folds.push((id, expr)));
+                    if found.len() < MAX_NODES {
+                        self.rewrites(node, &mut |expr| found.push((id, expr)));
+                    }
+                }
+            }
+            found.sort();
+            found.dedup_by(|(a_id, a), (b_id, b)| a_id == b_id && PartialEq::eq(&*a, &*b));
+
+            // Folds never make the graph much bigger, so they are all
+            // applied even once the other rewrites have run out of room.
+            let mut changed = false;
+            for (id, expr) in folds.into_iter().chain(found) {
+                if changed && self.size() > MAX_NODES {
+                    break;
+                }
+                let new = self.add_expr(expr.rf());
+                changed |= self.union(id, new);
+            }
+
+            self.rebuild();
+            if !changed || self.size() > MAX_NODES {
+                break;
+            }
+        }
     }
 
-    fn visit_symbol(&mut self, sym: &Symbol) -> ControlFlow<()> {
-        todo!()
+    /// Calls `found` with the rewrites of `node` that make it smaller:
+    /// whatever `canonicalize` does to it on its own, such as folding
+    /// numbers and merging repeated factors, and collecting like terms.
+    fn folds(&self, node: &Expr, found: &mut dyn FnMut(Expr)) {
+        let canonical = canonicalize(self.with_constants(node));
+        if !PartialEq::eq(&canonical, node) {
+            found(canonical);
+        }
+
+        if node.is::<Plus>() {
+            self.collect_like_terms(&children(node), found);
+        }
     }
 
-    fn visit_plus(&mut self, plus: &Plus) -> ControlFlow<()> {
-        todo!()
+    /// `2 * x + 3 * x = 5 * x`, for all of the terms at once.
+    fn collect_like_terms(&self, terms: &[Id], found: &mut dyn FnMut(Expr)) {
+        let mut like: Vec<(Vec<Id>, Number)> = vec![];
+        for &term in terms {
+            let (coef, rest) = self.split_coefficient(term);
+            match like.iter_mut().find(|(other, _)| *other == rest) {
+                Some((_, sum)) => *sum = &*sum + &coef,
+                None => like.push((rest, coef)),
+            }
+        }
+
+        if like.len() < terms.len() {
+            let terms = like.into_iter().map(|(rest, coef)| {
+                let mut factors: Vec<Expr> = rest.into_iter().map(class).collect();
+                factors.push(Expr::new(coef));
+                Expr::new(Times::new(factors)) as Expr
+            });
+            found(canonicalize(Expr::new(Plus::new(terms.collect::<Vec<_>>()))));
+        }
+    }
+
+    /// Like [`split_coefficient`], for an e-class, with the rest given as
+    /// the e-classes of its factors.
+    fn split_coefficient(&self, id: Id) -> (Number, Vec<Id>) {
+        let number = |id: Id| self.nodes[id].iter().find_map(|n| n.downcast::<Number>().cloned());
+        if let Some(n) = number(id) {
+            return (n, vec![]);
+        }
+
+        for node in self.nodes[id].iter().filter(|n| n.is::<Times>()) {
+            let (numbers, rest): (Vec<Id>, Vec<Id>) = children(node).into_iter().partition(|&f| number(f).is_some());
+            if let [n] = numbers[..] {
+                return (number(n).unwrap(), rest);
+            }
+        }
+
+        ((**ONE).clone(), vec![id])
+    }
+
+    /// Calls `found` with every other expression that `node` can be
+    /// rewritten to.
+    fn rewrites(&self, node: &Expr, found: &mut dyn FnMut(Expr)) {
+        let args = children(node);
+        if let Some(plus) = node.downcast::<Plus>() {
+            self.flatten::<Plus>(&args, found, |terms| Expr::new(Plus::new(terms)));
+            if plus.terms().len() == args.len() {
+                self.factor(&args, found);
+            }
+        } else if let Some(times) = node.downcast::<Times>() {
+            self.flatten::<Times>(&args, found, |factors| Expr::new(Times::new(factors)));
+            if times.terms().len() == args.len() {
+                self.distribute(&args, found);
+                self.merge_powers(&args, found);
+            }
+        } else if let (true, [base, exp]) = (node.is::<Pow>(), &args[..]) {
+            self.pow_laws(*base, *exp, found);
+        }
+    }
+
+    /// Replaces the args of `node` that are known to be numbers with
+    /// those numbers, so that `canonicalize` can fold them.
+    fn with_constants(&self, node: &Expr) -> Expr {
+        let args = children(node);
+        if args.is_empty() {
+            return node.clone();
+        }
+
+        let args = args
+            .into_iter()
+            .map(|arg| self.nodes[arg].iter().find(|n| n.is::<Number>()).cloned().unwrap_or_else(|| class(arg)))
+            .collect();
+        rebuild(node.rf(), args)
     }
 
-    fn visit_times(&mut self, times: &Times) -> ControlFlow<()> {
-        todo!()
+    /// `a + (b + c) = a + b + c`, and the same for products.
+    fn flatten<T: Basic>(&self, args: &[Id], found: &mut dyn FnMut(Expr), make: fn(Vec<Expr>) -> Expr) {
+        for (i, &arg) in args.iter().enumerate() {
+            for inner in self.nodes[arg].iter().filter(|n| n.is::<T>()) {
+                let mut flat: Vec<Expr> = args.iter().map(|&a| class(a)).collect();
+                flat.remove(i);
+                flat.extend(children(inner).into_iter().map(class));
+                found(make(flat));
+            }
+        }
+    }
+
+    /// `a * (b + c) = a * b + a * c`
+    fn distribute(&self, args: &[Id], found: &mut dyn FnMut(Expr)) {
+        for (i, &arg) in args.iter().enumerate() {
+            for sum in self.nodes[arg].iter().filter(|n| n.is::<Plus>()) {
+                let mut others: Vec<Expr> = args.iter().map(|&a| class(a)).collect();
+                others.remove(i);
+
+                let terms = children(sum).into_iter().map(|term| {
+                    let mut factors = others.clone();
+                    factors.push(class(term));
+                    Expr::new(Times::new(factors)) as Expr
+                });
+                found(Expr::new(Plus::new(terms.collect::<Vec<_>>())));
+            }
+        }
+    }
+
+    /// The ways of writing the e-class `id` as a product, with `id`
+    /// itself being `id * 1`.
+    fn products(&self, id: Id) -> Vec<Vec<Id>> {
+        let mut products = vec![vec![id]];
+        products.extend(self.nodes[id].iter().filter(|n| n.is::<Times>()).map(children));
+        products.sort();
+        products.dedup();
+        products
+    }
+
+    /// `a * b + a * c = a * (b + c)`, for any two of the terms.
+    fn factor(&self, args: &[Id], found: &mut dyn FnMut(Expr)) {
+        let rest = |factors: &[Id], skip: usize| -> Expr {
+            let mut rest: Vec<Id> = factors.to_vec();
+            rest.remove(skip);
+            match rest.len() {
+                0 => ONE.clone(),
+                _ => Expr::new(Times::new(rest.into_iter().map(class).collect::<Vec<_>>())),
+            }
+        };
+
+        for i in 0..args.len() {
+            for j in i + 1..args.len() {
+                for lhs in self.products(args[i]) {
+                    for rhs in self.products(args[j]) {
+                        for (k, &common) in lhs.iter().enumerate() {
+                            let Some(l) = rhs.iter().position(|&f| f == common) else {
+                                continue;
+                            };
+
+                            let sum = Expr::new(Plus::new([rest(&lhs, k), rest(&rhs, l)]));
+                            let mut terms: Vec<Expr> = args.iter().map(|&a| class(a)).collect();
+                            terms.remove(j);
+                            terms[i] = Expr::new(Times::new([class(common), sum.into()]));
+                            found(Expr::new(Plus::new(terms)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The ways of writing the e-class `id` as a power, with `id`
+    /// itself being `id ^ 1`.
+    fn powers(&self, id: Id) -> Vec<(Id, Expr)> {
+        let mut powers = vec![(id, ONE.clone() as Expr)];
+        for node in self.nodes[id].iter().filter(|n| n.is::<Pow>()) {
+            if let [base, exp] = children(node)[..] {
+                powers.push((base, class(exp)));
+            }
+        }
+        powers
+    }
+
+    /// `x^a * x^b = x^(a + b)`
+    fn merge_powers(&self, args: &[Id], found: &mut dyn FnMut(Expr)) {
+        for i in 0..args.len() {
+            for j in i + 1..args.len() {
+                for (lhs_base, lhs_exp) in self.powers(args[i]) {
+                    for (rhs_base, rhs_exp) in self.powers(args[j]) {
+                        if lhs_base != rhs_base {
+                            continue;
+                        }
+
+                        let exp = Expr::new(Plus::new([lhs_exp.clone(), rhs_exp]));
+                        let mut factors: Vec<Expr> = args.iter().map(|&a| class(a)).collect();
+                        factors.remove(j);
+                        factors[i] = Expr::new(Pow::new(class(lhs_base), exp.into()));
+                        found(Expr::new(Times::new(factors)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// `(x^a)^n = x^(a * n)` and `(x * y)^n = x^n * y^n`, for integers `n`.
+    fn pow_laws(&self, base: Id, exp: Id, found: &mut dyn FnMut(Expr)) {
+        let integer = self.nodes[exp].iter().any(|n| n.downcast::<Number>().is_some_and(Number::is_integer));
+        if !integer {
+            return;
+        }
+
+        for node in &self.nodes[base] {
+            let args = children(node);
+            if let (true, [inner_base, inner_exp]) = (node.is::<Pow>(), &args[..]) {
+                let exp = Expr::new(Times::new([class(*inner_exp), class(exp)]));
+                found(Expr::new(Pow::new(class(*inner_base), exp.into())));
+            } else if node.is::<Times>() {
+                let factors = args.iter().map(|&f| Expr::new(Pow::new(class(f), class(exp))) as Expr);
+                found(Expr::new(Times::new(factors.collect::<Vec<_>>())));
+            }
+        }
+    }
+
+    /// Builds the cheapest expression in the e-class `root`.
+    fn extract<C: CostFunction + ?Sized>(&self, root: Id, cost: &mut C) -> Expr {
+        let mut best: Vec<Option<(C::Cost, Expr)>> = vec![None; self.parents.len()];
+        loop {
+            let mut changed = false;
+            for id in self.classes() {
+                for node in &self.nodes[id] {
+                    let args: Option<Vec<C::Cost>> = children(node)
+                        .iter()
+                        .map(|&arg| best[self.find(arg)].as_ref().map(|(c, _)| c.clone()))
+                        .collect();
+                    let Some(args) = args else {
+                        continue;
+                    };
+
+                    let node_cost = cost.cost(node.rf(), &args);
+                    let better = match &best[id] {
+                        Some((old, _)) => node_cost.partial_cmp(old) == Some(Ordering::Less),
+                        None => true,
+                    };
+                    if better {
+                        best[id] = Some((node_cost, node.clone()));
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        self.build(root, &best)
+    }
+
+    fn build<T>(&self, id: Id, best: &[Option<(T, Expr)>]) -> Expr {
+        let (_, node) = best[self.find(id)].as_ref().expect("every e-class has a finite cost");
+        let args = children(node);
+        if args.is_empty() {
+            return node.clone();
+        }
+
+        let args = args.into_iter().map(|arg| self.build(arg, best)).collect();
+        rebuild(node.rf(), args)
     }
 }
@@ -0,0 +1,74 @@
+use crate::{basic::Basic, canonicalize::canonicalize, expr::{Expr, ExprRef}, fold::rebuild, functions::Plus};
+
+/// Replaces each `old` with `new` in `expr`, one rule after another, so
+/// the replacements of earlier rules are substituted into by later ones.
+///
+/// Patterns are matched against the canonical form of `expr`. A sum
+/// pattern also matches a subset of the terms of a larger sum, so
+/// `a + b` is replaced inside `a + b + c`.
+pub fn subs(expr: Expr, rules: &[(Expr, Expr)]) -> Expr {
+    rules.iter().fold(canonicalize(expr), |expr, rule| {
+        subs_simultaneous(expr, std::slice::from_ref(rule))
+    })
+}
+
+/// Replaces each `old` with `new` in `expr`, all at once, so the
+/// replacements are never substituted into themselves.
+///
+/// `subs_simultaneous(x + y, &[(x, y), (y, x)])` swaps `x` and `y`, where
+/// [`subs`] would give `x + x`.
+pub fn subs_simultaneous(expr: Expr, rules: &[(Expr, Expr)]) -> Expr {
+    let rules: Vec<(Expr, Expr)> = rules
+        .iter()
+        .map(|(old, new)| (canonicalize(old.clone()), new.clone()))
+        .collect();
+
+    canonicalize(substitute(canonicalize(expr).rf(), &rules))
+}
+
+impl<A: Basic> Expr<A> {
+    /// See [`subs`].
+    pub fn subs(&self, rules: &[(Expr, Expr)]) -> Expr {
+        subs(self.clone(), rules)
+    }
+}
+
+impl Expr {
+    /// See [`subs`].
+    pub fn subs(&self, rules: &[(Expr, Expr)]) -> Expr {
+        subs(self.clone(), rules)
+    }
+}
+
+fn substitute(expr: ExprRef, rules: &[(Expr, Expr)]) -> Expr {
+    for (old, new) in rules {
+        if PartialEq::eq(&expr.into_expr(), old) {
+            return new.clone();
+        }
+    }
+
+    if let Some(plus) = expr.downcast::<Plus>() {
+        for (old, new) in rules {
+            if let Some(rest) = old.downcast::<Plus>().and_then(|old| remove_terms(plus.terms(), old.terms())) {
+                let mut terms: Vec<Expr> = rest.into_iter().map(|term| substitute(term.rf(), rules)).collect();
+                terms.push(new.clone());
+                return Expr::new(Plus::new(terms));
+            }
+        }
+    }
+
+    let args = expr.args().iter().map(|arg| substitute(arg.rf(), rules)).collect();
+    rebuild(expr, args)
+}
+
+/// Removes each of `pattern` from `terms`, or returns `None` when they
+/// aren't all there.
+fn remove_terms(terms: &[Expr], pattern: &[Expr]) -> Option<Vec<Expr>> {
+    let mut rest = terms.to_vec();
+    for term in pattern {
+        let i = rest.iter().position(|t| PartialEq::eq(t, term))?;
+        rest.remove(i);
+    }
+
+    Some(rest)
+}
@@ -2,23 +2,30 @@ use std::{cmp::Ordering, ops::ControlFlow};
 
 use string_cache::DefaultAtom;
 
-use crate::{Number, basic::Basic, expr::{Expr, ExprRef}, functions::{Derivative, Plus, Times}, undefined::Undefined, visitor::Visitor};
+use crate::{Number, basic::Basic, expr::{Expr, ExprRef}, constant::Constant, functions::{Acos, Acosh, AppliedFunction, Asin, Asinh, Atan, Atan2, Atanh, Cos, Cosh, Cot, Csc, Derivative, Exp, Log, Plus, Pow, Sec, Sin, Sinh, Tan, Tanh, Times}, pattern::Wild, undefined::Undefined, visitor::Visitor};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Symbol {
     name: DefaultAtom,
+    real: bool,
 }
 
 impl Symbol {
-    fn new(s: &str) -> Self {
+    fn new(s: &str, real: bool) -> Self {
         Self {
             name: DefaultAtom::from(s),
+            real,
         }
     }
 
     pub fn as_str(&self) -> &str {
         self.name.as_ref()
     }
+
+    /// Whether this symbol was declared with [`real_sym`].
+    pub fn is_real(&self) -> bool {
+        self.real
+    }
 }
 
 impl Basic for Symbol {
@@ -35,16 +42,19 @@ impl Basic for Symbol {
     }
 
     fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
-        if let Some(other) = other.downcast::<Self>() {
-            Some(Ord::cmp(self, other))
-        } else {
-            None
-        }
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
     }
 }
 
 pub fn sym(s: &str) -> Expr<Symbol> {
-    Expr::new(Symbol::new(s))
+    Expr::new(Symbol::new(s, false))
+}
+
+/// A symbol that is assumed to only take real values.
+///
+/// This is a different symbol from `sym(s)` with the same name.
+pub fn real_sym(s: &str) -> Expr<Symbol> {
+    Expr::new(Symbol::new(s, true))
 }
 
 pub fn depends_on(basic: ExprRef, sym: &Symbol) -> bool {
@@ -53,7 +63,7 @@ pub fn depends_on(basic: ExprRef, sym: &Symbol) -> bool {
         contains: false,
     };
 
-    basic.visit(&mut visitor);
+    let _ = basic.visit(&mut visitor);
 
     visitor.contains
 }
@@ -81,6 +91,14 @@ impl Visitor for DependentSymbolVisitor<'_> {
         }
     }
 
+    fn visit_wild(&mut self, _wild: ExprRef<Wild>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_constant(&mut self, _c: ExprRef<Constant>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
     fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()> {
         for term in plus.terms() {
             term.rf().visit(self)?;
@@ -97,8 +115,105 @@ impl Visitor for DependentSymbolVisitor<'_> {
         ControlFlow::Continue(())
     }
 
+    fn visit_pow(&mut self, pow: ExprRef<Pow>) -> ControlFlow<()> {
+        pow.base().visit(self)?;
+        pow.exp().visit(self)
+    }
+
+    fn visit_exp(&mut self, exp: ExprRef<Exp>) -> ControlFlow<()> {
+        exp.arg().visit(self)
+    }
+
+    fn visit_log(&mut self, log: ExprRef<Log>) -> ControlFlow<()> {
+        if let Some(base) = log.base() {
+            base.visit(self)?;
+        }
+        log.arg().visit(self)
+    }
+
+    fn visit_sin(&mut self, sin: ExprRef<Sin>) -> ControlFlow<()> {
+        sin.arg().visit(self)
+    }
+
+    fn visit_cos(&mut self, cos: ExprRef<Cos>) -> ControlFlow<()> {
+        cos.arg().visit(self)
+    }
+
+    fn visit_tan(&mut self, tan: ExprRef<Tan>) -> ControlFlow<()> {
+        tan.arg().visit(self)
+    }
+
+    fn visit_cot(&mut self, cot: ExprRef<Cot>) -> ControlFlow<()> {
+        cot.arg().visit(self)
+    }
+
+    fn visit_sec(&mut self, sec: ExprRef<Sec>) -> ControlFlow<()> {
+        sec.arg().visit(self)
+    }
+
+    fn visit_csc(&mut self, csc: ExprRef<Csc>) -> ControlFlow<()> {
+        csc.arg().visit(self)
+    }
+
+    fn visit_asin(&mut self, asin: ExprRef<Asin>) -> ControlFlow<()> {
+        asin.arg().visit(self)
+    }
+
+    fn visit_acos(&mut self, acos: ExprRef<Acos>) -> ControlFlow<()> {
+        acos.arg().visit(self)
+    }
+
+    fn visit_atan(&mut self, atan: ExprRef<Atan>) -> ControlFlow<()> {
+        atan.arg().visit(self)
+    }
+
+    fn visit_atan2(&mut self, atan2: ExprRef<Atan2>) -> ControlFlow<()> {
+        atan2.y().visit(self)?;
+        atan2.x().visit(self)
+    }
+
+    fn visit_sinh(&mut self, sinh: ExprRef<Sinh>) -> ControlFlow<()> {
+        sinh.arg().visit(self)
+    }
+
+    fn visit_cosh(&mut self, cosh: ExprRef<Cosh>) -> ControlFlow<()> {
+        cosh.arg().visit(self)
+    }
+
+    fn visit_tanh(&mut self, tanh: ExprRef<Tanh>) -> ControlFlow<()> {
+        tanh.arg().visit(self)
+    }
+
+    fn visit_asinh(&mut self, asinh: ExprRef<Asinh>) -> ControlFlow<()> {
+        asinh.arg().visit(self)
+    }
+
+    fn visit_acosh(&mut self, acosh: ExprRef<Acosh>) -> ControlFlow<()> {
+        acosh.arg().visit(self)
+    }
+
+    fn visit_atanh(&mut self, atanh: ExprRef<Atanh>) -> ControlFlow<()> {
+        atanh.arg().visit(self)
+    }
+
+    fn visit_applied_function(&mut self, f: ExprRef<AppliedFunction>) -> ControlFlow<()> {
+        for arg in f.args() {
+            arg.rf().visit(self)?;
+        }
+
+        ControlFlow::Continue(())
+    }
+
     fn visit_derivative(&mut self, derivative: ExprRef<Derivative>) -> ControlFlow<()> {
         derivative.wrt().visit(self)?;
         derivative.expr().visit(self)
     }
+
+    fn visit_other(&mut self, other: ExprRef) -> ControlFlow<()> {
+        for arg in other.args() {
+            arg.rf().visit(self)?;
+        }
+
+        ControlFlow::Continue(())
+    }
 }
@@ -19,10 +19,6 @@ impl Basic for Undefined {
     }
 
     fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
-        if let Some(other) = other.downcast::<Self>() {
-            Some(Ord::cmp(self, other))
-        } else {
-            None
-        }
+        other.downcast::<Self>().map(|other| Ord::cmp(self, other))
     }
 }
\ No newline at end of file
@@ -1,14 +1,100 @@
 use std::ops::ControlFlow;
 
-use crate::{Number, expr::{Expr, ExprRef}, functions::{Derivative, Plus, Times}, symbol::Symbol, undefined::Undefined};
-
+use crate::{Number, constant::Constant, expr::ExprRef, pattern::Wild, functions::{Acos, Acosh, AppliedFunction, Asin, Asinh, Atan, Atan2, Atanh, Cos, Cosh, Cot, Csc, Derivative, Exp, Log, Plus, Pow, Sec, Sin, Sinh, Tan, Tanh, Times}, symbol::Symbol, undefined::Undefined};
 
+/// Dispatches on the type of a node, through `Basic::visit`.
+///
+/// Every method falls back to `visit_other`, so a visitor only needs
+/// to override the node types it treats specially.
 pub trait Visitor {
-    fn visit_undefined(&mut self, undef: ExprRef<Undefined>) -> ControlFlow<()>;
-    fn visit_number(&mut self, n: ExprRef<Number>) -> ControlFlow<()>;
-    fn visit_symbol(&mut self, sym: ExprRef<Symbol>) -> ControlFlow<()>;
-    fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()>;
-    fn visit_times(&mut self, times: ExprRef<Times>) -> ControlFlow<()>;
+    fn visit_undefined(&mut self, undef: ExprRef<Undefined>) -> ControlFlow<()> {
+        self.visit_other(undef)
+    }
+    fn visit_number(&mut self, n: ExprRef<Number>) -> ControlFlow<()> {
+        self.visit_other(n)
+    }
+    fn visit_symbol(&mut self, sym: ExprRef<Symbol>) -> ControlFlow<()> {
+        self.visit_other(sym)
+    }
+    fn visit_wild(&mut self, wild: ExprRef<Wild>) -> ControlFlow<()> {
+        self.visit_other(wild)
+    }
+    fn visit_constant(&mut self, c: ExprRef<Constant>) -> ControlFlow<()> {
+        self.visit_other(c)
+    }
+    fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()> {
+        self.visit_other(plus)
+    }
+    fn visit_times(&mut self, times: ExprRef<Times>) -> ControlFlow<()> {
+        self.visit_other(times)
+    }
+    fn visit_pow(&mut self, pow: ExprRef<Pow>) -> ControlFlow<()> {
+        self.visit_other(pow)
+    }
+    fn visit_exp(&mut self, exp: ExprRef<Exp>) -> ControlFlow<()> {
+        self.visit_other(exp)
+    }
+    fn visit_log(&mut self, log: ExprRef<Log>) -> ControlFlow<()> {
+        self.visit_other(log)
+    }
+    fn visit_sin(&mut self, sin: ExprRef<Sin>) -> ControlFlow<()> {
+        self.visit_other(sin)
+    }
+    fn visit_cos(&mut self, cos: ExprRef<Cos>) -> ControlFlow<()> {
+        self.visit_other(cos)
+    }
+    fn visit_tan(&mut self, tan: ExprRef<Tan>) -> ControlFlow<()> {
+        self.visit_other(tan)
+    }
+    fn visit_cot(&mut self, cot: ExprRef<Cot>) -> ControlFlow<()> {
+        self.visit_other(cot)
+    }
+    fn visit_sec(&mut self, sec: ExprRef<Sec>) -> ControlFlow<()> {
+        self.visit_other(sec)
+    }
+    fn visit_csc(&mut self, csc: ExprRef<Csc>) -> ControlFlow<()> {
+        self.visit_other(csc)
+    }
+    fn visit_asin(&mut self, asin: ExprRef<Asin>) -> ControlFlow<()> {
+        self.visit_other(asin)
+    }
+    fn visit_acos(&mut self, acos: ExprRef<Acos>) -> ControlFlow<()> {
+        self.visit_other(acos)
+    }
+    fn visit_atan(&mut self, atan: ExprRef<Atan>) -> ControlFlow<()> {
+        self.visit_other(atan)
+    }
+    fn visit_atan2(&mut self, atan2: ExprRef<Atan2>) -> ControlFlow<()> {
+        self.visit_other(atan2)
+    }
+    fn visit_sinh(&mut self, sinh: ExprRef<Sinh>) -> ControlFlow<()> {
+        self.visit_other(sinh)
+    }
+    fn visit_cosh(&mut self, cosh: ExprRef<Cosh>) -> ControlFlow<()> {
+        self.visit_other(cosh)
+    }
+    fn visit_tanh(&mut self, tanh: ExprRef<Tanh>) -> ControlFlow<()> {
+        self.visit_other(tanh)
+    }
+    fn visit_asinh(&mut self, asinh: ExprRef<Asinh>) -> ControlFlow<()> {
+        self.visit_other(asinh)
+    }
+    fn visit_acosh(&mut self, acosh: ExprRef<Acosh>) -> ControlFlow<()> {
+        self.visit_other(acosh)
+    }
+    fn visit_atanh(&mut self, atanh: ExprRef<Atanh>) -> ControlFlow<()> {
+        self.visit_other(atanh)
+    }
+    fn visit_applied_function(&mut self, f: ExprRef<AppliedFunction>) -> ControlFlow<()> {
+        self.visit_other(f)
+    }
+
+    fn visit_derivative(&mut self, derivative: ExprRef<Derivative>) -> ControlFlow<()> {
+        self.visit_other(derivative)
+    }
 
-    fn visit_derivative(&mut self, derivative: ExprRef<Derivative>) -> ControlFlow<()>;
+    /// Called for node types that this trait has no method for, such as
+    /// ones defined outside of this crate, and by default for all the
+    /// others.
+    fn visit_other(&mut self, other: ExprRef) -> ControlFlow<()>;
 }
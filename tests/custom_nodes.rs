@@ -0,0 +1,89 @@
+#![feature(arbitrary_self_types)]
+
+use std::{cmp::Ordering, ops::ControlFlow};
+
+use das::{Basic, Expr, ExprRef, Number, Visitor, ONE, ZERO, canonicalize::canonicalize, convert::{Convert, LaTex}, eval_symbolic::symbolic_eval, functions::{Derivative, Plus, derivative}, symbol::{Symbol, depends_on, sym}};
+
+/// The Heaviside step function, which is one for positive arguments
+/// and zero for negative ones.
+#[derive(Debug)]
+struct Step(Expr);
+
+impl Basic for Step {
+    fn visit(self: ExprRef<Self>, visitor: &mut dyn Visitor) -> ControlFlow<()> {
+        visitor.visit_other(self)
+    }
+
+    fn eq(&self, other: &dyn Basic) -> bool {
+        other.downcast::<Self>().is_some_and(|other| PartialEq::eq(&self.0, &other.0))
+    }
+
+    fn cmp(&self, other: &dyn Basic) -> Option<Ordering> {
+        other.downcast::<Self>().map(|other| Ord::cmp(&self.0, &other.0))
+    }
+
+    fn args(&self) -> Vec<Expr> {
+        vec![self.0.clone()]
+    }
+
+    fn with_args(&self, mut args: Vec<Expr>) -> Option<Expr> {
+        Some(Expr::new(Step(args.pop()?)))
+    }
+
+    fn canonical(&self) -> Option<Expr> {
+        match self.0.downcast::<Number>()? {
+            n if n.is_negative() => Some(ZERO.clone().into()),
+            n if n.is_zero() => None,
+            _ => Some(ONE.clone().into()),
+        }
+    }
+
+    fn latex(&self, args: &[String]) -> Option<String> {
+        Some(format!("\\theta\\left({}\\right)", args[0]))
+    }
+}
+
+fn step(arg: impl Into<Expr>) -> Expr {
+    Expr::new(Step(arg.into()))
+}
+
+/// Counts the symbols in an expression, treating every other node as
+/// something to look inside of.
+struct CountSymbols(usize);
+
+impl Visitor for CountSymbols {
+    fn visit_symbol(&mut self, _sym: ExprRef<Symbol>) -> ControlFlow<()> {
+        self.0 += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn visit_plus(&mut self, plus: ExprRef<Plus>) -> ControlFlow<()> {
+        plus.terms().iter().try_for_each(|term| term.rf().visit(self))
+    }
+
+    fn visit_other(&mut self, other: ExprRef) -> ControlFlow<()> {
+        other.args().iter().try_for_each(|arg| arg.rf().visit(self))
+    }
+}
+
+#[test]
+fn passes_handle_custom_nodes() {
+    let x = sym("x");
+    let y = sym("y");
+    let s = step(&x - 1);
+
+    assert!(depends_on(s.rf(), &x));
+    assert!(!depends_on(s.rf(), &y));
+    assert_eq!(&canonicalize(step(Expr::from(-2i32))), &(ZERO.clone() as Expr));
+    assert_eq!(&canonicalize(step(Expr::from(3i32) * 2)), &(ONE.clone() as Expr));
+    assert_eq!(LaTex::convert_to_string(canonicalize(s.clone())), "\\theta\\left(-1 + x\\right)");
+
+    // with no derivative hook the derivative is left unevaluated
+    let prime = symbolic_eval(derivative(s.clone(), x.clone()).into());
+    assert!(prime.is::<Derivative>());
+    assert_eq!(&symbolic_eval(derivative(s.clone(), y).into()), &(ZERO.clone() as Expr));
+
+    let mut count = CountSymbols(0);
+    let _ = canonicalize(s * &x + step(&x)).rf().visit(&mut count);
+    assert_eq!(count.0, 3);
+}